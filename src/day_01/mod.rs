@@ -1,16 +1,16 @@
+use crate::utils::input::sections;
+
 
 pub struct CalorieCounting {
     data: Vec<Vec<u32>>
 }
 
 impl crate::Advent for CalorieCounting {
-    fn new(data: &str) -> Self {
-        let elf_supplies: Vec<&str> = data.lines().collect();
-        let data = elf_supplies
-            .split(|line| line.is_empty())
+    fn new(data: &str, _params: &crate::Params) -> Self {
+        let data = sections(data)
             .map(|vals| {
                 vals
-                    .iter()
+                    .lines()
                     .map(|val| val.parse().unwrap())
                     .collect()
             })
@@ -119,7 +119,7 @@ impl FromStr for Shape {
 }
 
 impl crate::Advent for RockPaperScissors {
-    fn new(data: &str) -> Self {
+    fn new(data: &str, _params: &crate::Params) -> Self {
         let data = data
             .lines()
             .map(|l| {
@@ -48,7 +48,7 @@ fn char_priority(c: char) -> usize {
 }
 
 impl crate::Advent for RucksackReorganization {
-    fn new(data: &str) -> RucksackReorganization {
+    fn new(data: &str, _params: &crate::Params) -> RucksackReorganization {
         let data = data.lines()
             .map(|l| {
                 l.split_at(l.len() / 2 )
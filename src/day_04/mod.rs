@@ -1,11 +1,13 @@
 
 use std::ops::RangeInclusive;
+use crate::utils::intervals::RangeSet;
+
 pub struct CampCleanup {
     data: Vec<(RangeInclusive<u32>, RangeInclusive<u32>)>
 }
 
 impl crate::Advent for CampCleanup {
-    fn new(data: &str) -> Self {
+    fn new(data: &str, _params: &crate::Params) -> Self {
         let data = data
             .lines()
             .map(|l| {
@@ -20,26 +22,17 @@ impl crate::Advent for CampCleanup {
     }
 
     fn part_01(&self) -> String {
-        let mut fully_contained_sum: usize = 0;
-        for pair in &self.data {
-            let lhs_ord = pair.0.start().cmp(&pair.1.start());
-            let rhs_ord = pair.0.end().cmp(&pair.1.end());
-            if lhs_ord != rhs_ord || lhs_ord.is_eq() || rhs_ord.is_eq() {
-                fully_contained_sum += 1;
-            }
-        }
+        let fully_contained_sum = self.data.iter().filter(|(lhs, rhs)| {
+            RangeSet::from(lhs.clone()).contains_range(rhs.clone()) ||
+            RangeSet::from(rhs.clone()).contains_range(lhs.clone())
+        }).count();
         fully_contained_sum.to_string()
     }
 
     fn part_02(&self) -> String {
-        let mut num_overlap: usize = 0;
-        for pair in &self.data {
-            let lhs_ord = pair.0.end().cmp(&pair.1.start());
-            let rhs_ord = pair.0.start().cmp(&pair.1.end());
-            if !(lhs_ord.is_lt() || rhs_ord.is_gt()) {
-                num_overlap += 1; 
-            }
-        }
+        let num_overlap = self.data.iter().filter(|(lhs, rhs)| {
+            RangeSet::from(lhs.clone()).overlaps(rhs.clone())
+        }).count();
         num_overlap.to_string()
     }
-}
\ No newline at end of file
+}
@@ -1,21 +1,25 @@
+use crate::utils::parse;
+use anyhow::anyhow;
+
 pub struct SupplyStacks {
     stacks: Vec<Vec<char>>,
     instructions: Vec<(usize, usize, usize)>
 }
 
 impl crate::Advent for SupplyStacks {
-    fn new(data: &str) -> Self {
-        let data: Vec<&str> = data.lines().collect();
-        let data: Vec<_> = data.split(|e| e.is_empty()).collect();
+    fn new(data: &str, _params: &crate::Params) -> Self {
+        let mut sections = parse::sections(data);
+        let (drawing, instructions) = (sections.next().unwrap(), sections.next().unwrap());
+        let drawing: Vec<&str> = drawing.lines().collect();
         
         let mut stacks: Vec<Vec<char>> = vec![];
-        let length = data[0].iter().rev().next().unwrap().len();
+        let length = drawing.last().unwrap().len();
         let width = (length + 1) / 4;        
         for _ in 0..width {
             stacks.push(vec![]);
         }
 
-        data[0].iter().rev().skip(1).for_each(|line| {  
+        drawing.iter().rev().skip(1).for_each(|line| {  
             let all_chars: Vec<char> = line.chars().collect();
             all_chars
                 .chunks(4)
@@ -26,14 +30,16 @@ impl crate::Advent for SupplyStacks {
                 });
         });
 
-        let instructions: Vec<(usize, usize, usize)> = data[1].iter().map(|l| {
-            let parts: Vec<_> = l.split(' ').collect();
-            (
-                parts[1].parse().unwrap(), 
-                parts[3].parse::<usize>().unwrap() - 1, 
-                parts[5].parse::<usize>().unwrap() - 1
-            )
-        }).collect();
+        let instructions: Vec<(usize, usize, usize)> = parse::lines(instructions, |l| {
+            let [quant, from, to] = parse::template("move {} from {} to {}", l)?;
+            let stack = |number: &str| {
+                parse::value::<usize>(number)?
+                    .checked_sub(1)
+                    .filter(|i| *i < width)
+                    .ok_or_else(|| anyhow!("Stacks are numbered from 1 to {}, got {}", width, number))
+            };
+            Ok((parse::value(quant)?, stack(from)?, stack(to)?))
+        }).unwrap();
 
         SupplyStacks {
             stacks,
@@ -49,7 +55,7 @@ impl crate::Advent for SupplyStacks {
         }
         let mut result: Vec<char> = vec![];
         for stack in stacks {
-            result.push(stack.last().unwrap_or(&' ').clone())
+            result.push(*stack.last().unwrap_or(&' '))
         }
         result.into_iter().collect()
     }
@@ -63,7 +69,7 @@ impl crate::Advent for SupplyStacks {
         }
         let mut result: Vec<char> = vec![];
         for stack in stacks {
-            result.push(stack.last().unwrap_or(&' ').clone())
+            result.push(*stack.last().unwrap_or(&' '))
         }
         result.into_iter().collect()
     }
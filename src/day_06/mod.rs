@@ -30,7 +30,7 @@ impl TuningTrouble {
 }
 
 impl crate::Advent for TuningTrouble {
-    fn new(data: &str) -> Self {
+    fn new(data: &str, _params: &crate::Params) -> Self {
         let data: Vec<char> = data.lines().next().unwrap().chars().collect();
         TuningTrouble { data }
     }
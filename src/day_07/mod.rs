@@ -1,11 +1,13 @@
-use crate::utils::tree::Node;
-use std::{fmt, sync::Arc};
+use crate::utils::tree::{Tree, NodeId};
+use crate::utils::parse;
+use std::collections::BTreeMap;
+use std::fmt;
+use anyhow::{Result, anyhow, bail};
 
 #[derive(Debug)]
 struct Directory {
     name: String,
-    size: Option<u64>,
-    file_sizes: Vec<u64>
+    files: BTreeMap<String, u64>
 }
 
 impl fmt::Display for Directory {
@@ -18,97 +20,267 @@ impl Directory {
     fn new(name: impl Into<String>) -> Self {
         Directory {
             name: name.into(),
-            size: None,
-            file_sizes: vec![],
+            files: BTreeMap::new()
         }
     }
 }
 
-pub struct NoSpaceLeftOnDevice {
-    root: Node<Directory>
+/// Directories and files seen in the terminal output, the root is `/`.
+#[derive(Debug)]
+struct FileSystem {
+    tree: Tree<Directory>
 }
 
-impl crate::Advent for NoSpaceLeftOnDevice {
-    fn new(data: &str) -> Self {
+impl FileSystem {
+    fn new() -> Self {
+        FileSystem { tree: Tree::new(Directory::new("/")) }
+    }
 
-        let dir = Directory::new("/".to_string());
-        let root: Node<Directory> = Node::new(dir);
-        let mut current = Node {
-            arc_ref: root.get_copy_of_internal_arc()
-        };
+    fn child(&self, id: NodeId, name: &str) -> Option<NodeId> {
+        self.tree.children(id).iter().copied().find(|c| self.tree.get(*c).name == name)
+    }
+
+    /// Absolute path of a directory, like `/a/e`.
+    fn path(&self, id: NodeId) -> String {
+        let mut names = vec![];
+        let mut current = id;
+        while let Some(parent) = self.tree.parent(current) {
+            names.push(self.tree.get(current).name.as_str());
+            current = parent;
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    /// Directory at an absolute path.
+    fn find(&self, path: &str) -> Result<NodeId> {
+        let relative = path.strip_prefix('/').ok_or_else(|| anyhow!("Path {} isn't absolute", path))?;
+        relative.split('/').filter(|name| !name.is_empty()).try_fold(self.tree.root(), |id, name| {
+            self.child(id, name).ok_or_else(|| anyhow!("No directory {} in {}", name, self.path(id)))
+        })
+    }
+
+    fn add_directory(&mut self, parent: NodeId, name: &str) -> Result<()> {
+        if self.tree.get(parent).files.contains_key(name) {
+            bail!("{} is already listed as a file in {}", name, self.path(parent));
+        }
+        // Listing the same directory again mustn't add it twice
+        if self.child(parent, name).is_none() {
+            self.tree.add_child(parent, Directory::new(name));
+        }
+        Ok(())
+    }
+
+    fn add_file(&mut self, parent: NodeId, name: &str, size: u64) -> Result<()> {
+        if self.child(parent, name).is_some() {
+            bail!("{} is already listed as a directory in {}", name, self.path(parent));
+        }
+        let path = self.path(parent);
+        match self.tree.get_mut(parent).files.insert(name.to_string(), size) {
+            Some(previous) if previous != size => {
+                bail!("File {} in {} listed with sizes {} and {}", name, path, previous, size)
+            },
+            _ => Ok(())
+        }
+    }
 
-        for l in data.lines().skip(1) {
-            if l.starts_with("$") {
-                match &l[2..4] {
-                    "ls" => { continue; },
-                    "cd" => {
-                        match &l[5..] {
-                            ".." => {
-                                current = Node { arc_ref: current.get_parent().unwrap() };
-                            },
-                            name => {
-                                let children = &current.get_copy_of_internal_arc().children;
-                                let index = children.read().unwrap().iter().position(|c| {
-                                    c.value.write().unwrap().name == name
-                                });
-                                let child = Node { 
-                                    arc_ref: Arc::clone(&children.read().unwrap()[index.unwrap()])
-                                };
-                                current = child;
-                            }
-                        }
-                    },
-                    _ => panic!("Invalid line: {}", l)
-                }
-            } else {
-                let (lhs, name) = l.split_once(" ").unwrap();
-                match lhs {
-                    "dir" => {                        
-                        current.create_and_add_child(Directory::new(name));                        
-                    },
-                    num_str => {
-                        let size: u64 = num_str.parse().unwrap();                     
-                        current.arc_ref.value.write().unwrap().file_sizes.push(size);
-                    }
-                }
+    /// Total size of every directory below `id`, children come before their
+    /// parent so `id` is last.
+    fn sizes(&self, id: NodeId) -> Vec<(NodeId, u64)> {
+        self.tree.fold_postorder(id, |_id, dir, sub_dir_sizes| {
+            dir.files.values().sum::<u64>() + sub_dir_sizes.iter().sum::<u64>()
+        })
+    }
+
+    fn directory_sizes(&self) -> Vec<(NodeId, u64)> {
+        self.sizes(self.tree.root())
+    }
+
+    fn total_size(&self, path: &str) -> Result<u64> {
+        Ok(self.sizes(self.find(path)?).last().unwrap().1)
+    }
+
+    fn used_space(&self) -> u64 {
+        self.total_size("/").unwrap()
+    }
+
+    /// Smallest directory that frees at least `space` when deleted.
+    fn smallest_to_free(&self, space: u64) -> Option<(NodeId, u64)> {
+        self.directory_sizes()
+            .into_iter()
+            .filter(|(_, size)| *size >= space)
+            .min_by_key(|(_, size)| *size)
+    }
+
+    /// Sizes of the directories like `du`, every directory below its parent.
+    fn du(&self) -> String {
+        let sizes: BTreeMap<NodeId, u64> = self.directory_sizes().into_iter().collect();
+        let width = self.used_space().to_string().len();
+        let mut result = String::new();
+        for (level, id) in self.tree.inorder_iter(self.tree.root()) {
+            result += &format!("{:>width$}  {:indent$}{}\n", sizes[&id], "", self.path(id), indent = level * 2);
+        }
+        result
+    }
+}
+
+/// Replays a terminal session onto a [`FileSystem`].
+struct Shell {
+    fs: FileSystem,
+    cwd: NodeId,
+    listing: bool
+}
+
+impl Shell {
+    fn new() -> Self {
+        let fs = FileSystem::new();
+        let cwd = fs.tree.root();
+        Shell { fs, cwd, listing: false }
+    }
+
+    fn exec(&mut self, line: &str) -> Result<()> {
+        if let Some(command) = line.strip_prefix("$ ") {
+            self.listing = false;
+            match command.split_once(' ') {
+                None if command == "ls" => self.listing = true,
+                Some(("cd", target)) => self.cd(target)?,
+                _ => bail!("Unknown command {:?}", command)
             }
+            return Ok(());
+        }
+        if !self.listing {
+            bail!("Output outside of ls");
+        }
+        match line.split_once(' ') {
+            Some(("dir", name)) => self.fs.add_directory(self.cwd, name),
+            Some((size, name)) => self.fs.add_file(self.cwd, name, parse::value(size)?),
+            None => bail!("Expected a directory or a file")
+        }
+    }
+
+    fn cd(&mut self, target: &str) -> Result<()> {
+        self.cwd = match target {
+            // Like a real shell, the root is its own parent
+            ".." => self.fs.tree.parent(self.cwd).unwrap_or(self.fs.tree.root()),
+            path if path.starts_with('/') => self.fs.find(path)?,
+            name => self.fs.child(self.cwd, name)
+                .ok_or_else(|| anyhow!("No directory {} in {}", name, self.fs.path(self.cwd)))?
         };
-        NoSpaceLeftOnDevice { root }
+        Ok(())
+    }
+}
+
+fn parse_session(data: &str) -> Result<FileSystem> {
+    let mut shell = Shell::new();
+    parse::lines(data, |l| shell.exec(l))?;
+    Ok(shell.fs)
+}
+
+pub struct NoSpaceLeftOnDevice {
+    fs: FileSystem,
+    disk_size: u64,
+    required_space: u64,
+    du: bool
+}
+
+impl crate::Advent for NoSpaceLeftOnDevice {
+    fn new(data: &str, params: &crate::Params) -> Self {
+        NoSpaceLeftOnDevice {
+            fs: parse_session(data).unwrap(),
+            disk_size: params.get("disk_size"),
+            required_space: params.get("required_space"),
+            du: params.get("du")
+        }
+    }
+
+    fn parameters() -> &'static [crate::Parameter] {
+        const PARAMETERS: &[crate::Parameter] = &[
+            crate::Parameter::new::<u64>("disk_size", "70000000", "70000000"),
+            crate::Parameter::new::<u64>("required_space", "30000000", "30000000"),
+            crate::Parameter::new::<bool>("du", "false", "false"),
+        ];
+        PARAMETERS
     }
 
     fn part_01(&self) -> String {
-        let mut sub_100k_sizes_sum: u64 = 0;
-        for node in self.root.inrevorder_iter() {            
-            let sub_dirs_size: u64 = node.children.read().unwrap().iter().map(|n| {
-                n.value.read().unwrap().size.unwrap()
-            }).sum();
-            let files_size: u64 = node.value.read().unwrap().file_sizes.iter().sum();
-            let size_sum = sub_dirs_size + files_size;
-            if size_sum < 100_000 {
-                sub_100k_sizes_sum += size_sum
-            }
-            node.value.write().unwrap().size = Some(size_sum);
+        if self.du {
+            print!("{}", self.fs.du());
         }
-        sub_100k_sizes_sum.to_string()
+        let small_sizes_sum: u64 = self.fs.directory_sizes()
+            .into_iter()
+            .map(|(_, size)| size)
+            .filter(|size| *size <= 100_000)
+            .sum();
+        small_sizes_sum.to_string()
     }
-    
+
     fn part_02(&self) -> String {
+        let free_space = self.disk_size.saturating_sub(self.fs.used_space());
+        let size_to_delete = self.required_space.saturating_sub(free_space);
+        if size_to_delete == 0 {
+            return "0".to_string();
+        }
+        let (_, size) = self.fs.smallest_to_free(size_to_delete)
+            .unwrap_or_else(|| panic!("No directory frees {} on its own", size_to_delete));
+        size.to_string()
+    }
+}
 
-        let root_size = self.root.value.read().unwrap().size.unwrap();        
-        let total_available: u64 = 70_000_000;
-        let required_size: u64 = 30_000_000;
-        let size_left = total_available - root_size;
-        let size_to_delete = required_size - size_left;
-
-        let mut nodes: Vec<u64> = self.root.inrevorder_iter().filter_map(|n| {
-            let size = n.value.read().unwrap().size.unwrap();
-            if size > size_to_delete {
-                Some(size)
-            } else {
-                None
-            }
-        }).collect();
-        nodes.sort_unstable();
-        nodes[0].to_string()
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let fs = parse_session(include_str!("example.txt")).unwrap();
+        assert_eq!(fs.total_size("/").unwrap(), 48381165);
+        assert_eq!(fs.total_size("/a").unwrap(), 94853);
+        assert_eq!(fs.total_size("/a/e/").unwrap(), 584);
+        assert!(fs.total_size("/x").is_err());
+        let (id, size) = fs.smallest_to_free(8381165).unwrap();
+        assert_eq!((fs.path(id), size), ("/d".to_string(), 24933642));
+        assert_eq!(fs.du(), "\
+48381165  /
+   94853    /a
+     584      /a/e
+24933642    /d
+");
+    }
+
+    #[test]
+    fn test_navigation() {
+        // Back to the root in the middle of the session, `..` at the root
+        // and listing the same directory twice
+        let fs = parse_session("\
+$ cd /
+$ ls
+dir a
+$ cd a
+$ ls
+10 f
+dir b
+$ cd /
+$ cd ..
+$ cd /a/b
+$ ls
+5 g
+$ cd /a
+$ ls
+dir b
+10 f").unwrap();
+        assert_eq!(fs.total_size("/").unwrap(), 15);
+        assert_eq!(fs.tree.len(), 3);
+        assert_eq!(fs.path(fs.find("/a/b").unwrap()), "/a/b");
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = parse_session("$ ls\n10 f\n$ ls\n20 f").unwrap_err();
+        assert_eq!(format!("{:#}", error), "Line 4: \"20 f\": File f in / listed with sizes 10 and 20");
+        assert!(parse_session("$ ls\ndir f\n10 f").is_err());
+        assert!(parse_session("$ cd a").is_err());
+        assert!(parse_session("10 f").is_err());
+        assert!(parse_session("$ rm f").is_err());
     }
-}
\ No newline at end of file
+}
@@ -1,173 +1,172 @@
-use std::{str::FromStr, fmt};
-use crate::utils::{point::*, wait_user_input};
+use std::fmt;
+use std::collections::HashSet;
+use std::num::NonZeroUsize;
+use anyhow::Result;
+use crate::utils::{parse, point::*};
+
+type C = Coord<isize>;
+type Script = Vec<(Direction, usize)>;
 
 pub struct RopeBridge {
-    commands: Vec<(Direction, usize)>
+    commands: Script,
+    knots_part_01: usize,
+    knots_part_02: usize
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct ParseDirectionError;
-impl FromStr for Direction {
-    type Err = ParseDirectionError;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "U" => Ok(Direction::N),
-            "D" => Ok(Direction::S),
-            "L" => Ok(Direction::W),
-            "R" => Ok(Direction::E),
-            _ => Err(ParseDirectionError)
+impl Coord<isize> {
+    /// Where a knot ends up after its leader moved, it only moves once the
+    /// two stop touching.
+    fn follow(&self, leader: &Coord<isize>) -> Coord<isize> {
+        let (dx, dy) = (leader.x - self.x, leader.y - self.y);
+        if dx.abs() <= 1 && dy.abs() <= 1 {
+            return *self;
         }
+        Coord::new(self.x + dx.signum(), self.y + dy.signum())
     }
 }
 
-impl Coord<isize> {
-    fn move_to(&mut self, direction: &Direction) {
-        let dir_vec: Coord<isize> = direction.into();
-        *self += dir_vec;
-    }
-
-    fn tail_need_to_move(&self, tail: &Coord<isize>) -> Option<Direction> {
-        let x_diff = self.x - tail.x;
-        let y_diff = self.y - tail.y;
-        match (x_diff, y_diff) {
-            ( 2,  2) => Some(Direction::NE),
-            ( 2, -2) => Some(Direction::SE),
-            (-2,  2) => Some(Direction::NW),
-            (-2, -2) => Some(Direction::SW),
-
-            ( 2,  _) => Some(Direction::E),
-            (-2,  _) => Some(Direction::W),
-            ( _,  2) => Some(Direction::N),
-            ( _, -2) => Some(Direction::S),
-            _ => None
-        }
-    }
+/// Parses movement lines like `R 4`.
+fn parse_script(data: &str) -> Result<Script> {
+    parse::lines(data, |l| {
+        let [dir, steps] = parse::template("{} {}", l)?;
+        Ok((dir.parse()?, parse::value(steps)?))
+    })
+}
 
-    fn update_tail(&self, tail: &mut Coord<isize>, direction: &Direction) {
-        let axes: Vec<Axis> = direction.affected_axes();
-        let dir_vec: Coord<isize> = direction.into();
-        for axis in &axes {            
-            *tail.get_mut(&axis) += dir_vec.get(axis);            
-        }
-        if axes.len() == 1 {
-            let other_axis = axes[0].other();
-            *tail.get_mut(&other_axis) = *self.get(&other_axis);
+impl crate::Advent for RopeBridge {
+    fn new(data: &str, params: &crate::Params) -> Self {
+        RopeBridge {
+            commands: parse_script(data).unwrap(),
+            knots_part_01: params.get::<NonZeroUsize>("knots_part_01").get(),
+            knots_part_02: params.get::<NonZeroUsize>("knots_part_02").get()
         }
     }
-}
 
-impl crate::Advent for RopeBridge {
-    fn new(data: &str) -> Self {
-        let commands: Vec<(Direction, usize)> = data
-            .lines()
-            .map(|l| {
-                let (dir, num) = l.split_once(" ").unwrap();
-                (Direction::from_str(dir).unwrap(), num.parse().unwrap())                
-            })
-            .collect();
-        RopeBridge { commands }
+    fn parameters() -> &'static [crate::Parameter] {
+        const PARAMETERS: &[crate::Parameter] = &[
+            // A rope needs at least its head
+            crate::Parameter::new::<NonZeroUsize>("knots_part_01", "2", "2"),
+            crate::Parameter::new::<NonZeroUsize>("knots_part_02", "10", "10"),
+        ];
+        PARAMETERS
     }
 
     fn part_01(&self) -> String {
-        let mut rope = Rope { 
-            head: Coord::new(0, 0),
-            tail: vec![Coord::new(0, 0)]
-        };
-        let mut end_positions: Vec<Coord<isize>> = vec![Coord::new(0, 0)];
-
-        for (dir, steps) in &self.commands {            
-            let mut new_end_positions = rope.exec_command(dir, *steps);
-            end_positions.append(&mut new_end_positions);
-        }
-
-        end_positions.sort_unstable();
-        end_positions.dedup();
-        end_positions.len().to_string()
+        let mut rope = Rope::new(self.knots_part_01);
+        rope.run(&self.commands);
+        rope.visited(rope.tail()).len().to_string()
     }
 
     fn part_02(&self) -> String {
-        let mut rope = Rope { 
-            head: Coord::new(0, 0),
-            tail: (0..9).map(|_| Coord::new(0, 0)).into_iter().collect()
-        };
-        let mut end_positions: Vec<Coord<isize>> = vec![Coord::new(0, 0)];
-
-        for (dir, steps) in &self.commands {            
-            let mut new_end_positions = rope.exec_command(dir, *steps);
-            end_positions.append(&mut new_end_positions);        
-            // rope.print_and_wait();
-        }
-
-        end_positions.sort_unstable();
-        end_positions.dedup();
-        end_positions.len().to_string()    
+        let mut rope = Rope::new(self.knots_part_02);
+        rope.run(&self.commands);
+        rope.visited(rope.tail()).len().to_string()
     }
 }
 
 
+/// Knots of a rope, the first one is the head. Every knot remembers all
+/// positions it has been at.
 struct Rope {
-    head: Coord<isize>,
-    tail: Vec<Coord<isize>>    
+    knots: Vec<C>,
+    visited: Vec<HashSet<C>>
 }
 
 impl Rope {
-    #[allow(dead_code)]
-    fn print_and_wait(&self) {
-        print!("{esc}c", esc = 27 as char);
-        println!("{:?}", self);    
-        wait_user_input();     
+    fn new(knots: usize) -> Self {
+        Self::from_knots(vec![Coord::new(0, 0); knots])
     }
 
-    fn exec_command(&mut self, dir: &Direction, steps: usize) -> Vec<Coord<isize>> {
-        let mut end_positions: Vec<Coord<isize>> = vec![];
-        let last_index = self.tail.len() - 1;
-        for _step in 0..steps {
+    fn from_knots(knots: Vec<C>) -> Self {
+        assert!(!knots.is_empty(), "A rope needs at least a head");
+        let visited = knots.iter().map(|k| HashSet::from([*k])).collect();
+        Rope { knots, visited }
+    }
 
-            self.head.move_to(&dir);
-            let mut current = &mut self.head;
-            for (i, part) in self.tail.iter_mut().enumerate() {                
-                let part_needs_to_move = current.tail_need_to_move(part);
+    /// Index of the last knot.
+    fn tail(&self) -> usize {
+        self.knots.len() - 1
+    }
 
-                let Some(direction) = part_needs_to_move else {
-                    break;
-                };
+    fn visited(&self, knot: usize) -> &HashSet<C> {
+        &self.visited[knot]
+    }
 
-                current.update_tail(part, &direction);
-                current = part;
-                if i == last_index {
-                    end_positions.push(current.clone())
-                }                
+    fn step(&mut self, direction: &Direction) {
+        self.knots[0] += direction.delta();
+        self.visited[0].insert(self.knots[0]);
+        for i in 1..self.knots.len() {
+            let knot = self.knots[i].follow(&self.knots[i - 1]);
+            if knot == self.knots[i] {
+                // The rest of the rope doesn't move either
+                break;
             }
-                     
+            self.knots[i] = knot;
+            self.visited[i].insert(knot);
+        }
+    }
+
+    fn exec_command(&mut self, direction: &Direction, steps: usize) {
+        for _ in 0..steps {
+            self.step(direction);
+        }
+    }
+
+    fn run(&mut self, script: &[(Direction, usize)]) {
+        for (direction, steps) in script {
+            self.exec_command(direction, *steps);
+        }
+    }
+
+    /// Draws the smallest area holding all `coords` and the start, `label`
+    /// picks the char of every cell.
+    fn render<'a>(coords: impl Iterator<Item = &'a C> + Clone, label: impl Fn(C) -> char) -> String {
+        let coords = coords.copied().chain(std::iter::once(Coord::new(0, 0)));
+        let min_x = coords.clone().map(|c| c.x).min().unwrap();
+        let max_x = coords.clone().map(|c| c.x).max().unwrap();
+        let min_y = coords.clone().map(|c| c.y).min().unwrap();
+        let max_y = coords.map(|c| c.y).max().unwrap();
+        let mut result = String::new();
+        for y in (min_y..=max_y).rev() {
+            result.extend((min_x..=max_x).map(|x| label(Coord::new(x, y))));
+            result.push('\n');
         }
-        end_positions
+        result
+    }
+
+    /// The rope with its head as `H`, the other knots numbered in base 36
+    /// and the start as `s`.
+    fn render_knots(&self) -> String {
+        Rope::render(self.knots.iter(), |c| {
+            match self.knots.iter().position(|k| *k == c) {
+                Some(0) => 'H',
+                Some(i) => char::from_digit(i as u32, 36).unwrap_or('#'),
+                None if c == Coord::new(0, 0) => 's',
+                None => '.'
+            }
+        })
+    }
+
+    /// Positions a knot has been at as `#`, the start as `s`.
+    fn render_visited(&self, knot: usize) -> String {
+        let visited = &self.visited[knot];
+        Rope::render(visited.iter(), |c| {
+            if c == Coord::new(0, 0) {
+                's'
+            } else if visited.contains(&c) {
+                '#'
+            } else {
+                '.'
+            }
+        })
     }
 }
 
 impl fmt::Debug for Rope {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let min: isize = -15;
-        let max: isize = 15;
-        write!(f, "\n")?;
-        for y in (min..=max).rev() {
-            let s: String = (min..=max).map(|x| {
-                let current: Coord<isize> = Coord::new(x, y);
-                if self.head == current {
-                    return 'H';
-                }
-                if let Some(pos) = self.tail.iter().position(|coord| {
-                    *coord == current
-                }) {
-                    return char::from_digit((pos + 1) as u32, 10).unwrap_or('#');
-                }
-                if current.x == 0 && current.y == 0 {
-                    return 'X';
-                }
-                '.'
-            }).collect();
-            write!(f, "{}\n", s)?;
-        }
-        write!(f, "\n")
+        writeln!(f)?;
+        writeln!(f, "{}", self.render_knots())?;
+        write!(f, "{}", self.render_visited(self.tail()))
     }
 }
 
@@ -178,84 +177,74 @@ mod tests {
 
     #[test]
     fn test_east() {
-        let mut rope = Rope {
-            head: Coord::new(0, 0),
-            tail: vec![Coord::new(0, 0)]
-        };    
-        let tail_positions = rope.exec_command(&Direction::E, 4); 
-        assert_eq!(tail_positions, vec![
-            Coord::new(-1,0),
-            Coord::new(-2,0),
-            Coord::new(-3,0),
-        ]);
-        assert_eq!(rope.head, Coord::new(-4, 0));
-        assert_eq!(rope.tail[0], Coord::new(-3, 0));
-        
+        let mut rope = Rope::new(2);
+        rope.exec_command(&Direction::E, 4);
+        assert_eq!(rope.knots[0], Coord::new(4, 0));
+        assert_eq!(rope.knots[1], Coord::new(3, 0));
+        assert_eq!(rope.visited(1).len(), 4);
+
+        // A diagonal tail catches up onto the row of the head
+        let mut rope = Rope::from_knots(vec![Coord::new(0, 0), Coord::new(0, -1)]);
+        rope.exec_command(&Direction::E, 4);
+        assert_eq!(rope.knots[0], Coord::new(4, 0));
+        assert_eq!(rope.knots[1], Coord::new(3, 0));
+        assert_eq!(rope.visited(1), &HashSet::from([
+            Coord::new(0, -1),
+            Coord::new(1, 0),
+            Coord::new(2, 0),
+            Coord::new(3, 0),
+        ]));
+    }
 
-        let mut rope = Rope {
-            head: Coord::new(0, 0),
-            tail: vec![Coord::new(0, -1)]
-        };    
-        let tail_positions = rope.exec_command(&Direction::E, 4); 
-        assert_eq!(tail_positions, vec![
-            Coord::new(-1,0),
-            Coord::new(-2,0),
-            Coord::new(-3,0),
-        ]);
-        assert_eq!(rope.head, Coord::new(-4, 0));
-        assert_eq!(rope.tail[0], Coord::new(-3, 0));
+    #[test]
+    fn test_small_example() {
+        let script = parse_script(include_str!("example.txt")).unwrap();
+        let mut rope = Rope::new(10);
+        rope.run(&script);
+        assert_eq!(rope.render_knots(), "\
+.1H3
+.5..
+6...
+");
+        assert_eq!(rope.visited(rope.tail()).len(), 1);
+        // The knot after the head moves just like the tail of a short rope
+        assert_eq!(rope.visited(1).len(), 13);
+
+        let mut rope = Rope::new(2);
+        rope.run(&script);
+        assert_eq!(rope.render_visited(1), "\
+..##.
+...##
+.####
+....#
+s###.
+");
     }
 
     #[test]
-    fn visual_test_small_example() {            
-        let mut rope = Rope {
-            head: Coord::new(0, 0),
-            tail: (0..9).map(|_| Coord::new(0, 0)).into_iter().collect()
-        };    
-        let commands: Vec<(Direction, usize)> = vec![
-            (Direction::E, 4),
-            (Direction::N, 4),
-            (Direction::W, 3),
-            (Direction::S, 1),
-            (Direction::E, 4),
-            (Direction::S, 1),
-            (Direction::W, 5),
-            (Direction::E, 2),
-        ];
-        for (dir, steps) in &commands {
-            rope.exec_command(dir, *steps);
-            rope.print_and_wait();
-        }
+    fn test_big_example() {
+        let script = parse_script("R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20").unwrap();
+        let mut rope = Rope::new(10);
+        rope.run(&script);
+        assert_eq!(rope.visited(rope.tail()).len(), 36);
+        assert_eq!(rope.render_visited(rope.tail()).matches('#').count(), 35);
+        // Labels past 9 keep going with letters
+        let mut long = Rope::new(12);
+        assert_eq!(long.render_knots(), "H\n");
+        long.exec_command(&Direction::E, 11);
+        assert_eq!(long.render_knots(), "ba987654321H\n");
     }
 
     #[test]
-    fn test_big_example() {            
-        let mut rope = Rope {
-            head: Coord::new(0, 0),
-            tail: (0..9).map(|_| Coord::new(0, 0)).into_iter().collect()
-        };    
-        let commands: Vec<(Direction, usize)> = vec![
-            (Direction::E, 5),
-            (Direction::N, 8),
-            (Direction::W, 8),
-            (Direction::S, 3),
-            (Direction::E, 17),
-            (Direction::S, 10),
-            (Direction::W, 25),
-            (Direction::N, 20),
-        ];
-        let mut tail_positions: Vec<Coord<isize>> = vec![Coord::new(0,0)];
-        for (dir, steps) in &commands {
-            let mut new_tail_pos = rope.exec_command(dir, *steps);            
-            tail_positions.append(&mut new_tail_pos);
-        }
-        assert_eq!(tail_positions.len(), 36);
-        let tail_pos_rope = Rope {
-            head: tail_positions[0].clone(),
-            tail: tail_positions.into_iter().skip(1).collect()
-        };
-        
-        tail_pos_rope.print_and_wait();
+    fn test_knot_count() {
+        use crate::Advent;
+        use std::collections::HashMap;
+        let overrides = HashMap::from([("knots_part_01".to_string(), "0".to_string())]);
+        let error = crate::Params::resolve(RopeBridge::parameters(), true, &overrides).unwrap_err();
+        assert_eq!(format!("{:#}", error), "Invalid value \"0\" for parameter knots_part_01: number would be zero for non-zero type");
+        let overrides = HashMap::from([("knots_part_01".to_string(), "1".to_string())]);
+        let params = crate::Params::resolve(RopeBridge::parameters(), true, &overrides).unwrap();
+        let bridge = RopeBridge::new("R 4", &params);
+        assert_eq!(bridge.part_01(), "5");
     }
 }
-
@@ -1,105 +1,230 @@
+use std::num::NonZeroUsize;
 use std::str::FromStr;
-use anyhow::{Error, Result, Context};
+use anyhow::{Error, Result, anyhow};
+use crate::utils::{ocr, parse};
 
 pub struct CathodeRayTube {
-    instructions: Vec<Instruction>
+    instructions: Vec<Instruction>,
+    crt_width: usize,
+    crt_height: usize
+}
+
+const REGISTERS: usize = 26;
+
+/// Register named by a lowercase letter, `x` is the one driving the sprite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Register(usize);
+
+impl Register {
+    const X: Register = Register(23);
+}
+
+impl TryFrom<char> for Register {
+    type Error = Error;
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        match value {
+            'a'..='z' => Ok(Register(value as usize - 'a' as usize)),
+            _ => Err(anyhow!("Invalid register {}", value))
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
 enum Instruction {
-    Add(isize),
+    Add(Register, isize),
     Noop
 }
 
 impl Instruction {
     fn cycles(&self) -> usize {
         match self {
-            Self::Add(_) => 2,
+            Self::Add(_, _) => 2,
             Self::Noop => 1
         }
     }
+
+    fn execute(&self, registers: &mut [isize; REGISTERS]) {
+        match self {
+            Self::Add(register, value) => registers[register.0] += value,
+            Self::Noop => {}
+        }
+    }
 }
 
 impl FromStr for Instruction {
     type Err = Error;
-    
+
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "noop" => Ok(Self::Noop),
-            _ => {
-                let string = s.split_once(" ").context("Invalid command")?;
-                let num: isize = string.1.parse()?;
-                Ok(Self::Add(num))
-            }
+        if s == "noop" {
+            return Ok(Self::Noop);
         }
+        let [register, value] = parse::template("add{} {}", s)?;
+        let mut chars = register.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(Self::Add(Register::try_from(c)?, parse::value(value)?)),
+            _ => Err(anyhow!("Invalid instruction {}", s))
+        }
+    }
+}
+
+/// Machine state during a cycle, the first cycle is 1.
+#[derive(Debug, Clone, PartialEq)]
+struct State {
+    cycle: usize,
+    registers: [isize; REGISTERS]
+}
+
+impl State {
+    fn x(&self) -> isize {
+        self.registers[Register::X.0]
+    }
+
+    fn signal_strength(&self) -> isize {
+        self.cycle as isize * self.x()
     }
 }
 
+/// Runs a program one cycle at a time, instructions only take effect once
+/// all of their cycles are done.
+struct Cpu<'a> {
+    program: &'a [Instruction],
+    registers: [isize; REGISTERS],
+    pc: usize,
+    cycle: usize,
+    // Cycles the current instruction has been running for
+    busy: usize
+}
+
+impl<'a> Cpu<'a> {
+    fn new(program: &'a [Instruction]) -> Self {
+        let mut registers = [0; REGISTERS];
+        registers[Register::X.0] = 1;
+        Cpu { program, registers, pc: 0, cycle: 0, busy: 0 }
+    }
+
+    /// States at the given cycles only.
+    fn breakpoints(self, cycles: &'a [usize]) -> impl Iterator<Item = State> + 'a {
+        let last = cycles.iter().max().copied().unwrap_or(0);
+        self.take_while(move |s| s.cycle <= last).filter(move |s| cycles.contains(&s.cycle))
+    }
+}
+
+impl<'a> Iterator for Cpu<'a> {
+    type Item = State;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let instruction = self.program.get(self.pc)?;
+        self.cycle += 1;
+        let state = State { cycle: self.cycle, registers: self.registers };
+        self.busy += 1;
+        if self.busy == instruction.cycles() {
+            instruction.execute(&mut self.registers);
+            self.pc += 1;
+            self.busy = 0;
+        }
+        Some(state)
+    }
+}
+
+/// Screen lit by a 3 pixel wide sprite centered on `x`, drawing one pixel
+/// per cycle row by row.
+fn draw(states: impl Iterator<Item = State>, width: usize, height: usize) -> Vec<Vec<bool>> {
+    let mut screen = vec![vec![false; width]; height];
+    for state in states.take(width * height) {
+        let pixel = state.cycle - 1;
+        let column = (pixel % width) as isize;
+        screen[pixel / width][pixel % width] = (state.x() - 1..=state.x() + 1).contains(&column);
+    }
+    screen
+}
+
+fn render(screen: &[Vec<bool>]) -> String {
+    let mut result = String::from("\n");
+    for row in screen {
+        result.extend(row.iter().map(|lit| if *lit { '#' } else { '.' }));
+        result.push('\n');
+    }
+    result
+}
+
 impl crate::Advent for CathodeRayTube {
-    fn new(data: &str) -> Self {
-        let instructions = data
-            .lines()
-            .map(|l| {
-                Instruction::from_str(l).unwrap()
-            }).collect();
-        CathodeRayTube { instructions }
+    fn new(data: &str, params: &crate::Params) -> Self {
+        let instructions = parse::lines(data, Instruction::from_str).unwrap();
+        CathodeRayTube {
+            instructions,
+            crt_width: params.get::<NonZeroUsize>("crt_width").get(),
+            crt_height: params.get::<NonZeroUsize>("crt_height").get()
+        }
+    }
+
+    fn parameters() -> &'static [crate::Parameter] {
+        const PARAMETERS: &[crate::Parameter] = &[
+            crate::Parameter::new::<NonZeroUsize>("crt_width", "40", "40"),
+            crate::Parameter::new::<NonZeroUsize>("crt_height", "6", "6"),
+        ];
+        PARAMETERS
     }
 
     fn part_01(&self) -> String {
-        let mut x: isize = 1;
-        let mut cycles: usize = 0;
-        let mut results: Vec<isize> = vec![];
-        for instruction in self.instructions.iter() {
-            let next_instruction_cycles = cycles + instruction.cycles();
-            for i in cycles + 1..=next_instruction_cycles {
-                if (i - 20) % 40 == 0 {
-                    results.push(i as isize * x);
-                }
-            }
-            
-            match instruction {
-                Instruction::Add(val) => {
-                    x += val;
-                },
-                _ => {}
-            }
-            cycles = next_instruction_cycles;
-        }
-        results.iter().sum::<isize>().to_string()
+        Cpu::new(&self.instructions)
+            .breakpoints(&[20, 60, 100, 140, 180, 220])
+            .map(|s| s.signal_strength())
+            .sum::<isize>()
+            .to_string()
     }
 
     fn part_02(&self) -> String {
-        let mut x: isize = 1;
-        let mut cycles: usize = 0;
-        let mut crt: [char; 40 * 6] = ['.'; 40 * 6];
-        for instruction in self.instructions.iter() {
-            let next_instruction_cycles = cycles + instruction.cycles();
-            for i in cycles..next_instruction_cycles {
-                let vertical_pos = i % 40;
-                if (x - 1..=x + 1).contains(&(vertical_pos as isize)) {
-                    crt[i] = '#';
-                } else {
-                    crt[i] = '.';
-                }    
-            }
-            
-            match instruction {
-                Instruction::Add(val) => {
-                    x += val;
-                },
-                _ => {}
-            }
-            cycles = next_instruction_cycles;
+        let screen = draw(Cpu::new(&self.instructions), self.crt_width, self.crt_height);
+        // The example doesn't draw letters, show the picture instead
+        ocr::decode(&screen).unwrap_or_else(|_| render(&screen))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_states() {
+        let program: Vec<Instruction> = ["noop", "addx 3", "addx -5"].iter().map(|l| l.parse().unwrap()).collect();
+        let xs: Vec<(usize, isize)> = Cpu::new(&program).map(|s| (s.cycle, s.x())).collect();
+        assert_eq!(xs, vec![(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]);
+        let states: Vec<State> = Cpu::new(&program).breakpoints(&[3, 5]).collect();
+        assert_eq!(states.iter().map(|s| s.x()).collect::<Vec<_>>(), vec![1, 4]);
+    }
+
+    #[test]
+    fn test_other_registers() {
+        let program: Vec<Instruction> = ["adda 2", "addx 3"].iter().map(|l| l.parse().unwrap()).collect();
+        let last = Cpu::new(&program).last().unwrap();
+        assert_eq!((last.registers[0], last.x()), (2, 1));
+        assert!("addxy 3".parse::<Instruction>().is_err());
+        assert!("mul 3".parse::<Instruction>().is_err());
+    }
+
+    #[test]
+    fn test_example_screen() {
+        let program = parse::lines(include_str!("example.txt"), Instruction::from_str).unwrap();
+        let screen = draw(Cpu::new(&program), 40, 6);
+        assert_eq!(render(&screen), "
+##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######.....
+");
+    }
+
+    #[test]
+    fn test_empty_screen() {
+        use crate::Advent;
+        use std::collections::HashMap;
+        for name in ["crt_width", "crt_height"] {
+            let overrides = HashMap::from([(name.to_string(), "0".to_string())]);
+            assert!(crate::Params::resolve(CathodeRayTube::parameters(), true, &overrides).is_err());
         }
-        let mut result: String = String::from("\n");
-        for y in 0..6 {
-            let row: String = (0..40).map(|x| {
-                let index = y * 40 + x;
-                crt[index]
-            }).collect();
-            result.push_str(&row);
-            result.push_str("\n");
-        }        
-        result
     }
 }
@@ -1,16 +1,134 @@
 use std::fmt;
-use std::rc::Rc;
-use std::cell::RefCell;
 use std::collections::VecDeque;
+use std::str::FromStr;
+use anyhow::{anyhow, bail, Context, Error, Result};
+use crate::utils::parse;
 
 pub struct MonkeyInTheMiddle {
-    monkeys: Vec<Monkey>
+    monkeys: Vec<Monkey>,
+    rounds_part_01: usize,
+    rounds_part_02: usize,
+    reduction_part_01: WorryReduction,
+    reduction_part_02: WorryReduction
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Old,
+    Constant(u128)
+}
+
+impl Operand {
+    fn value(&self, old: u128) -> u128 {
+        match self {
+            Operand::Old => old,
+            Operand::Constant(value) => *value
+        }
+    }
+}
+
+/// Right hand side of `new = <lhs> <operator> <rhs>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Operation {
+    lhs: Operand,
+    operator: Operator,
+    rhs: Operand
+}
+
+impl Operation {
+    fn apply(&self, old: u128) -> Result<u128> {
+        let (lhs, rhs) = (self.lhs.value(old), self.rhs.value(old));
+        let result = match self.operator {
+            Operator::Add => lhs.checked_add(rhs),
+            Operator::Sub => lhs.checked_sub(rhs),
+            Operator::Mul => lhs.checked_mul(rhs),
+            Operator::Div => lhs.checked_div(rhs)
+        };
+        result.ok_or_else(|| anyhow!("Worry level out of range for {} with old = {}", self, old))
+    }
+
+    /// Whether the result modulo `m` only depends on the operands modulo
+    /// `m`, which holds for `+` and `*` only.
+    fn keeps_modulo(&self) -> bool {
+        matches!(self.operator, Operator::Add | Operator::Mul)
+    }
+}
+
+impl FromStr for Operator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "+" => Ok(Operator::Add),
+            "-" => Ok(Operator::Sub),
+            "*" => Ok(Operator::Mul),
+            "/" => Ok(Operator::Div),
+            _ => Err(anyhow!("Invalid sign {}", s))
+        }
+    }
+}
+
+impl FromStr for Operand {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "old" => Ok(Operand::Old),
+            num => Ok(Operand::Constant(parse::value(num)?))
+        }
+    }
+}
+
+impl FromStr for Operation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [lhs, operator, rhs] = parse::template("new = {} {} {}", s)?;
+        let operation = Operation {
+            lhs: lhs.parse()?,
+            operator: operator.parse()?,
+            rhs: rhs.parse()?
+        };
+        if operation.operator == Operator::Div && operation.rhs == Operand::Constant(0) {
+            bail!("Division by zero in {}", s);
+        }
+        Ok(operation)
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Old => write!(f, "old"),
+            Operand::Constant(value) => write!(f, "{}", value)
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operator = match self.operator {
+            Operator::Add => "+",
+            Operator::Sub => "-",
+            Operator::Mul => "*",
+            Operator::Div => "/"
+        };
+        write!(f, "new = {} {} {}", self.lhs, operator, self.rhs)
+    }
+}
+
+#[derive(Debug, Clone)]
 struct Monkey {
-    items: VecDeque<u128>,
-    operation: Rc<dyn Fn(u128) -> u128>,
+    items: Vec<u128>,
+    operation: Operation,
     test_num: u128,
     true_index: usize,
     false_index: usize,
@@ -18,109 +136,315 @@ struct Monkey {
 
 impl Monkey {
     fn test(&self, worry_level: u128) -> usize {
-        if worry_level % self.test_num == 0 {
+        if worry_level.is_multiple_of(self.test_num) {
             self.true_index
         } else {
             self.false_index
-        }        
+        }
+    }
+}
+
+impl FromStr for Monkey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = s.lines().map(str::trim).collect();
+        let [_header, items, operation, test, if_true, if_false] = lines[..] else {
+            return Err(anyhow!("Expected 6 lines, got {}", lines.len()));
+        };
+        let [items] = parse::template("Starting items: {}", items)?;
+        let [operation] = parse::template("Operation: {}", operation)?;
+        let [test_num] = parse::template("Test: divisible by {}", test)?;
+        let [true_index] = parse::template("If true: throw to monkey {}", if_true)?;
+        let [false_index] = parse::template("If false: throw to monkey {}", if_false)?;
+
+        Ok(Monkey {
+            items: parse::ints(items)?,
+            operation: operation.parse()?,
+            test_num: parse::value(test_num)?,
+            true_index: parse::value(true_index)?,
+            false_index: parse::value(false_index)?
+        })
+    }
+}
+
+/// How worry levels are kept in check after every inspection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WorryReduction {
+    Divide(u128),
+    /// Modulo the product of all test divisors, keeps every test result intact
+    ModuloProduct,
+    None
+}
+
+impl FromStr for WorryReduction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("divide", k)) => match parse::value(k)? {
+                0 => Err(anyhow!("Can't divide worry levels by 0")),
+                k => Ok(WorryReduction::Divide(k))
+            },
+            None if s == "modulo" => Ok(WorryReduction::ModuloProduct),
+            None if s == "none" => Ok(WorryReduction::None),
+            _ => Err(anyhow!("Invalid worry reduction {}, expected divide:<k>, modulo or none", s))
+        }
+    }
+}
+
+/// Inspection counts of every monkey after a round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Snapshot {
+    round: usize,
+    inspected: Vec<usize>
+}
+
+impl Snapshot {
+    fn monkey_business(&self) -> usize {
+        let mut inspected = self.inspected.clone();
+        inspected.sort_unstable();
+        inspected.iter().rev().take(2).product()
     }
 }
 
-impl fmt::Debug for Monkey {
+impl fmt::Display for Snapshot {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Monkey")
-            .field("items", &self.items)
-            .field("test_num", &self.test_num)
-            .field("true_index", &self.true_index)
-            .field("false_index", &self.false_index)
-            .finish()
+        writeln!(f, "== After round {} ==", self.round)?;
+        for (i, inspected) in self.inspected.iter().enumerate() {
+            writeln!(f, "Monkey {} inspected items {} times.", i, inspected)?;
+        }
+        Ok(())
     }
 }
 
-impl crate::Advent for MonkeyInTheMiddle {
-    fn new(data: &str) -> Self {
-        let lines: Vec<&str> = data.lines().collect();
-        let monkeys = lines
-            .split(|line| line.is_empty())
-            .map(|monkey_data| {
-                let mut it = monkey_data.iter().skip(1);
-                let (_lhs, rhs) = it.next().unwrap().split_once(": ").unwrap();
-                let items: VecDeque<u128> = rhs.split(", ").map(|n| n.parse().unwrap()).collect();
-                let (_lhs, rhs) = it.next().unwrap().split_once("= ").unwrap();                
-                let operation: Vec<_> = rhs.split(" ").collect();
-                let func: Rc<dyn Fn(u128) -> u128> = match operation[2] {
-                    "old" => {
-                        match operation[1] {
-                            "*" => Rc::new(move |old| old * old),
-                            "+" => Rc::new(move |old| old + old),
-                            _ => panic!("Invalid sign!")
-                        }
+struct MonkeyProgram<'a> {
+    monkeys: &'a [Monkey],
+    reduction: WorryReduction,
+    rounds: usize
+}
+
+/// Product of all test divisors, worry levels modulo it pass every test
+/// just like the full ones.
+fn modulus(monkeys: &[Monkey]) -> Result<u128> {
+    monkeys.iter().try_fold(1u128, |product, m| {
+        product.checked_mul(m.test_num)
+            .ok_or_else(|| anyhow!("Product of the test divisors doesn't fit into a u128"))
+    })
+}
+
+/// Checks that every monkey throws to an existing monkey and that the
+/// reductions keep the test results intact.
+fn validate(monkeys: &[Monkey], reductions: &[WorryReduction]) -> Result<()> {
+    if reductions.contains(&WorryReduction::ModuloProduct) {
+        modulus(monkeys)?;
+    }
+    for (i, monkey) in monkeys.iter().enumerate() {
+        if monkey.test_num == 0 {
+            bail!("Monkey {} tests divisibility by 0", i);
+        }
+        for target in [monkey.true_index, monkey.false_index] {
+            if target >= monkeys.len() {
+                bail!("Monkey {} throws to monkey {}, there are only {}", i, target, monkeys.len());
+            }
+        }
+        if reductions.contains(&WorryReduction::ModuloProduct) && !monkey.operation.keeps_modulo() {
+            bail!("Monkey {} does {}, modulo only works with + and *", i, monkey.operation);
+        }
+    }
+    Ok(())
+}
+
+impl<'a> MonkeyProgram<'a> {
+    fn new(monkeys: &'a [Monkey], reduction: WorryReduction, rounds: usize) -> Self {
+        MonkeyProgram { monkeys, reduction, rounds }
+    }
+
+    /// Snapshot after every round of the program.
+    fn snapshots(&self) -> Rounds<'_> {
+        let modulo = modulus(self.monkeys);
+        Rounds {
+            program: self,
+            items: self.monkeys.iter().map(|m| m.items.iter().copied().collect()).collect(),
+            inspected: vec![0; self.monkeys.len()],
+            round: 0,
+            modulo
+        }
+    }
+
+    fn run(&self) -> Result<Snapshot> {
+        let mut last = Snapshot { round: 0, inspected: vec![0; self.monkeys.len()] };
+        for snapshot in self.snapshots() {
+            last = snapshot?;
+        }
+        Ok(last)
+    }
+}
+
+struct Rounds<'a> {
+    program: &'a MonkeyProgram<'a>,
+    items: Vec<VecDeque<u128>>,
+    inspected: Vec<usize>,
+    round: usize,
+    modulo: Result<u128>
+}
+
+impl Rounds<'_> {
+    /// Ends the program after a monkey failed.
+    fn fail(&mut self, monkey: usize, error: Error) -> Option<Result<Snapshot>> {
+        let failed_round = self.round + 1;
+        self.round = self.program.rounds;
+        Some(Err(error.context(format!("Monkey {} in round {}", monkey, failed_round))))
+    }
+}
+
+impl<'a> Iterator for Rounds<'a> {
+    type Item = Result<Snapshot>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.round == self.program.rounds {
+            return None;
+        }
+        for (i, monkey) in self.program.monkeys.iter().enumerate() {
+            while let Some(item) = self.items[i].pop_front() {
+                self.inspected[i] += 1;
+                let worry_level = match monkey.operation.apply(item) {
+                    Ok(worry_level) => worry_level,
+                    Err(e) => return self.fail(i, e)
+                };
+                let worry_level = match (self.program.reduction, &self.modulo) {
+                    (WorryReduction::Divide(k), _) => worry_level / k,
+                    (WorryReduction::ModuloProduct, Ok(modulo)) if *modulo > 0 => worry_level % modulo,
+                    (WorryReduction::ModuloProduct, _) => {
+                        let error = match modulus(self.program.monkeys) {
+                            Err(e) => e,
+                            Ok(_) => anyhow!("A test divisor is 0")
+                        };
+                        return self.fail(i, error);
                     },
-                    num => {
-                        let num: u128 = num.parse().unwrap();
-                        match operation[1] {
-                            "*" => Rc::new(move |old| old * num),
-                            "+" => Rc::new(move |old| old + num),
-                            _ => panic!("Invalid sign!")
-                        }
-                    }
+                    (WorryReduction::None, _) => worry_level
                 };
-                let test_num: u128 = it.next().unwrap().split(" ").last().unwrap().parse().unwrap();
-                let true_index: usize = it.next().unwrap().split(" ").last().unwrap().parse().unwrap();
-                let false_index: usize = it.next().unwrap().split(" ").last().unwrap().parse().unwrap();
+                self.items[monkey.test(worry_level)].push_back(worry_level);
+            }
+        }
+        self.round += 1;
+        Some(Ok(Snapshot { round: self.round, inspected: self.inspected.clone() }))
+    }
+}
+
+impl crate::Advent for MonkeyInTheMiddle {
+    fn new(data: &str, params: &crate::Params) -> Self {
+        let monkeys = parse::sections(data)
+            .enumerate()
+            .map(|(i, monkey_data)| {
+                Monkey::from_str(monkey_data).with_context(|| format!("Invalid monkey {}", i))
+            })
+            .collect::<Result<Vec<Monkey>>>()
+            .unwrap();
+        let reduction_part_01 = params.get("reduction_part_01");
+        let reduction_part_02 = params.get("reduction_part_02");
+        validate(&monkeys, &[reduction_part_01, reduction_part_02]).unwrap();
+        MonkeyInTheMiddle {
+            monkeys,
+            rounds_part_01: params.get("rounds_part_01"),
+            rounds_part_02: params.get("rounds_part_02"),
+            reduction_part_01,
+            reduction_part_02
+        }
+    }
 
-                Monkey {
-                    items,
-                    operation: func,
-                    test_num,
-                    true_index,
-                    false_index
-                }
-            }).collect();
-        MonkeyInTheMiddle { monkeys }
+    fn parameters() -> &'static [crate::Parameter] {
+        const PARAMETERS: &[crate::Parameter] = &[
+            crate::Parameter::new::<usize>("rounds_part_01", "20", "20"),
+            crate::Parameter::new::<usize>("rounds_part_02", "10000", "10000"),
+            crate::Parameter::new::<WorryReduction>("reduction_part_01", "divide:3", "divide:3"),
+            crate::Parameter::new::<WorryReduction>("reduction_part_02", "modulo", "modulo"),
+        ];
+        PARAMETERS
     }
 
     fn part_01(&self) -> String {
-        let monkeys: Vec<_> = self.monkeys.clone().into_iter().map(|m| RefCell::new(m)).collect();
-        let mut inspected: Vec<usize> = (0..monkeys.len()).map(|_| 0).collect();
-
-        for _step in 0..20 {
-            
-            for (i, monkey) in monkeys.iter().enumerate() {
-                let mut monka = monkey.borrow_mut();
-                while let Some(item) = monka.items.pop_front() {
-                    let mut worry_level = (monka.operation)(item);
-                    inspected[i] += 1;
-                    worry_level /= 3;                                        
-                    let monkey_index = monka.test(worry_level);                    
-                    monkeys[monkey_index].borrow_mut().items.push_back(worry_level);
-                }
-            }        
-        }
-        inspected.sort_unstable();
-        inspected.iter().rev().take(2).fold(1, |axx, x| axx * x).to_string()
+        let program = MonkeyProgram::new(&self.monkeys, self.reduction_part_01, self.rounds_part_01);
+        program.run().unwrap().monkey_business().to_string()
     }
 
     fn part_02(&self) -> String {
-        let max_div = self.monkeys.iter().map(|m| m.test_num).fold(1, |agg, x| agg * x);
-
-        let monkeys: Vec<_> = self.monkeys.clone().into_iter().map(|m| RefCell::new(m)).collect();
-        let mut inspected: Vec<usize> = (0..monkeys.len()).map(|_| 0).collect();
-        for _step in 0..10_000 {            
-            for (i, monkey) in monkeys.iter().enumerate() {
-                let mut monka = monkey.borrow_mut();
-                while let Some(item) = monka.items.pop_front() {
-                    let mut worry_level = (monka.operation)(item);
-                    inspected[i] += 1;
-                    if worry_level > max_div {
-                        worry_level %= max_div;
-                    }
-                    let monkey_index = monka.test(worry_level);                    
-                    monkeys[monkey_index].borrow_mut().items.push_back(worry_level);
-                }
-            }
-        }
-        inspected.sort_unstable();
-        inspected.iter().rev().take(2).fold(1, |axx, x| axx * x).to_string()
+        let program = MonkeyProgram::new(&self.monkeys, self.reduction_part_02, self.rounds_part_02);
+        program.run().unwrap().monkey_business().to_string()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<Monkey> {
+        let data = include_str!("example.txt");
+        parse::sections(data).map(|m| m.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_operation() {
+        let operation: Operation = "new = old * old".parse().unwrap();
+        assert_eq!(operation.apply(7).unwrap(), 49);
+        let operation: Operation = "new = 10 - old".parse().unwrap();
+        assert_eq!(operation.apply(4).unwrap(), 6);
+        assert!(operation.apply(11).is_err());
+        assert_eq!(operation.to_string(), "new = 10 - old");
+        assert!("new = old / 0".parse::<Operation>().is_err());
+        assert!("new = old % 2".parse::<Operation>().is_err());
+    }
+
+    #[test]
+    fn test_worry_reduction() {
+        assert_eq!("divide:3".parse::<WorryReduction>().unwrap(), WorryReduction::Divide(3));
+        assert_eq!("modulo".parse::<WorryReduction>().unwrap(), WorryReduction::ModuloProduct);
+        assert_eq!("none".parse::<WorryReduction>().unwrap(), WorryReduction::None);
+        assert!("divide".parse::<WorryReduction>().is_err());
+        assert!("divide:0".parse::<WorryReduction>().is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut monkeys = example();
+        assert!(validate(&monkeys, &[WorryReduction::Divide(3), WorryReduction::ModuloProduct]).is_ok());
+        monkeys[1].operation = "new = old - 3".parse().unwrap();
+        assert!(validate(&monkeys, &[WorryReduction::Divide(3)]).is_ok());
+        let error = validate(&monkeys, &[WorryReduction::ModuloProduct]).unwrap_err();
+        assert_eq!(error.to_string(), "Monkey 1 does new = old - 3, modulo only works with + and *");
+        monkeys[0].test_num = 0;
+        let error = validate(&monkeys, &[WorryReduction::None]).unwrap_err();
+        assert_eq!(error.to_string(), "Monkey 0 tests divisibility by 0");
+        monkeys[0].test_num = u128::MAX / 2;
+        assert!(validate(&monkeys, &[WorryReduction::None]).is_ok());
+        let error = validate(&monkeys, &[WorryReduction::ModuloProduct]).unwrap_err();
+        assert_eq!(error.to_string(), "Product of the test divisors doesn't fit into a u128");
+        monkeys[0].test_num = 23;
+        monkeys[2].false_index = 4;
+        let error = validate(&monkeys, &[WorryReduction::None]).unwrap_err();
+        assert_eq!(error.to_string(), "Monkey 2 throws to monkey 4, there are only 4");
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let monkeys = example();
+        let program = MonkeyProgram::new(&monkeys, WorryReduction::ModuloProduct, 20);
+        let snapshots: Vec<Snapshot> = program.snapshots().collect::<Result<_>>().unwrap();
+        assert_eq!(snapshots.len(), 20);
+        assert_eq!(snapshots[0].inspected, vec![2, 4, 3, 6]);
+        assert_eq!(snapshots[19].inspected, vec![99, 97, 8, 103]);
+        assert!(snapshots[19].to_string().starts_with("== After round 20 ==\nMonkey 0 inspected items 99 times."));
+        let program = MonkeyProgram::new(&monkeys, WorryReduction::Divide(3), 20);
+        assert_eq!(program.run().unwrap().monkey_business(), 10605);
+        // Without any reduction the worry levels overflow long before
+        let program = MonkeyProgram::new(&monkeys, WorryReduction::None, 10000);
+        let error = program.run().unwrap_err();
+        assert!(error.to_string().starts_with("Monkey "), "{}", error);
+        let round: usize = error.to_string().rsplit(' ').next().unwrap().parse().unwrap();
+        assert!(round > 1 && round < 10000, "{}", error);
+        let snapshots: Vec<Result<Snapshot>> = program.snapshots().collect();
+        assert_eq!(snapshots.len(), round);
+        assert!(snapshots[round - 1].is_err());
     }
 }
@@ -13,7 +13,7 @@ pub struct HillClimbingAlhorithm {
 }
 
 impl crate::Advent for HillClimbingAlhorithm {
-    fn new(data: &str) -> Self {
+    fn new(data: &str, _params: &crate::Params) -> Self {
         let mut start = (0, 0);
         let mut end = (0, 0);
         let map: Vec<Vec<u32>> = data
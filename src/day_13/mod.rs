@@ -1,11 +1,12 @@
 use std::cmp::Ordering;
 use std::str::FromStr;
-use anyhow::{Error, Result};
+use anyhow::{Error, Result, Context, bail};
 use std::fmt;
+use crate::utils::input::sections;
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 enum Packet {
-    Number(u8),
+    Number(u64),
     List(Vec<Packet>)
 }
 
@@ -48,46 +49,83 @@ impl FromStr for Packet {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s = s.replace("10", "a");        
-        let mut packet = Self::List(vec![]);
-        let mut chars = s.chars();
-
-        while let Some(c) = chars.next() {
-            match c {
-                '[' => {
-                    let mut s = String::new();
-                    let mut depth = 1;
-
-                    while depth > 0 {
-                        let c = chars.next().unwrap();
-                        match c {
-                            '[' => depth += 1,
-                            ']' => depth -= 1,
-                            _ => {}
-                        }
-                        s.push(c);
-                    }
+        let mut parser = Parser { text: s.as_bytes(), position: 0 };
+        let packet = parser.list()?;
+        parser.skip_whitespace();
+        if parser.position != s.len() {
+            bail!("Unexpected {:?} after the packet at position {}", parser.text[parser.position] as char, parser.position);
+        }
+        Ok(packet)
+    }
+}
 
-                    if let Ok(sub_packet)  = s[..s.len() - 1].parse() {
-                        if let Self::List(list) = &mut packet {
-                            list.push(sub_packet);
-                        }
-                    }
-                },
-                ',' => {},
-                'a' => {
-                    if let Self::List(list) = &mut packet {
-                        list.push(Self::Number(10));
-                    }
-                },
+/// Recursive descent parser of the packet grammar
+/// `packet = list | number`, `list = "[" [packet {"," packet}] "]"`.
+struct Parser<'a> {
+    text: &'a [u8],
+    position: usize
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.text.get(self.position).is_some_and(|c| c.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.text.get(self.position).copied()
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<()> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += 1;
+                Ok(())
+            },
+            Some(c) => bail!("Expected {:?} at position {}, found {:?}", expected as char, self.position, c as char),
+            None => bail!("Expected {:?} at position {}, found the end", expected as char, self.position)
+        }
+    }
+
+    fn packet(&mut self) -> Result<Packet> {
+        match self.peek() {
+            Some(b'[') => self.list(),
+            Some(c) if c.is_ascii_digit() => self.number(),
+            Some(c) => bail!("Expected a packet at position {}, found {:?}", self.position, c as char),
+            None => bail!("Expected a packet at position {}, found the end", self.position)
+        }
+    }
+
+    fn list(&mut self) -> Result<Packet> {
+        self.expect(b'[')?;
+        let mut list = vec![];
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Packet::List(list));
+        }
+        loop {
+            list.push(self.packet()?);
+            match self.peek() {
+                Some(b',') => self.position += 1,
                 _ => {
-                    if let Self::List(list) = &mut packet {
-                        list.push(Self::Number(c.to_digit(10).unwrap() as u8));
-                    }
+                    self.expect(b']')?;
+                    return Ok(Packet::List(list));
                 }
             }
-        }        
-        Ok(packet)
+        }
+    }
+
+    fn number(&mut self) -> Result<Packet> {
+        let start = self.position;
+        while self.text.get(self.position).is_some_and(u8::is_ascii_digit) {
+            self.position += 1;
+        }
+        let digits = std::str::from_utf8(&self.text[start..self.position])?;
+        let number = digits.parse()
+            .with_context(|| format!("Number {} at position {} is too large", digits, start))?;
+        Ok(Packet::Number(number))
     }
 }
 
@@ -97,15 +135,10 @@ pub struct DistressSignal {
 }
 
 impl crate::Advent for DistressSignal {
-    fn new(data: &str) -> Self {
-        let lines: Vec<&str> = data.lines().collect();
-        let packets: Vec<_> = lines
-            .split(|l| l.is_empty())
+    fn new(data: &str, _params: &crate::Params) -> Self {
+        let packets: Vec<_> = sections(data)
             .map(|pair| {                
-                pair.iter().map(|part| {
-                    let packet = part.parse::<Packet>().unwrap();
-                    packet
-                }).collect::<Vec<Packet>>()
+                pair.lines().map(|part| part.parse::<Packet>().unwrap()).collect::<Vec<Packet>>()
             }).collect();     
         DistressSignal { 
             packets
@@ -138,3 +171,37 @@ impl crate::Advent for DistressSignal {
         (pos1 * pos2).to_string()
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for text in ["[]", "[[]]", "[1,[2,[3,[4,[5,6,7]]]],8,9]", "[10,[123456789012],0]", "[[[]],[]]"] {
+            assert_eq!(text.parse::<Packet>().unwrap().to_string(), text);
+        }
+        let spaced: Packet = " [ 1 , [ 2 ] ] ".parse().unwrap();
+        assert_eq!(spaced.to_string(), "[1,[2]]");
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let error = |s: &str| s.parse::<Packet>().unwrap_err().to_string();
+        assert_eq!(error("[1,2"), "Expected ']' at position 4, found the end");
+        assert_eq!(error("[1,,2]"), "Expected a packet at position 3, found ','");
+        assert_eq!(error("[1 2]"), "Expected ']' at position 3, found '2'");
+        assert_eq!(error("[1]]"), "Unexpected ']' after the packet at position 3");
+        assert_eq!(error("[99999999999999999999999]"), "Number 99999999999999999999999 at position 1 is too large");
+    }
+
+    #[test]
+    fn test_order() {
+        let packet = |s: &str| s.parse::<Packet>().unwrap();
+        assert!(packet("[1,1,3,1,1]") < packet("[1,1,5,1,1]"));
+        assert!(packet("[[1],[2,3,4]]") < packet("[[1],4]"));
+        assert!(packet("[9]") > packet("[[8,7,6]]"));
+        assert!(packet("[10]") > packet("[9]"));
+    }
+}
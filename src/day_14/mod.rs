@@ -1,9 +1,10 @@
-use itertools::Itertools;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use anyhow::{Result, bail};
 
-use crate::utils::point::{Coord, Grid};
-use crate::utils::wait_user_input;
-use std::{cmp, fmt, iter};
-type C = Coord<usize>;
+use crate::utils::point::Coord;
+use crate::utils::parse;
+type C = Coord<i32>;
 
 #[derive(Debug, PartialEq, Clone)]
 enum Material {
@@ -12,15 +13,6 @@ enum Material {
     Air
 }
 
-impl Material {
-    fn is_empty(&self) -> bool {
-        match self {
-            Material::Air => true,
-            _ => false
-        }
-    }
-}
-
 impl fmt::Display for Material {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -32,188 +24,258 @@ impl fmt::Display for Material {
 }
 
 pub struct RegolithReservoir {
-    grid: Grid<Material>,
-    offset_x: usize,
+    rocks: HashMap<C, Material>,
+    source: C,
+    columns: bool,
+    frame_every: usize
 }
 
 impl crate::Advent for RegolithReservoir {
-    fn new(data: &str) -> Self {
-        let mut rocks: Vec<Vec<C>> = data
-            .lines()
-            .map(|l| {
-                l.split(" -> ").map(|line| {
-                    let mut it = line.split(",").map(|v| v.parse().unwrap());
-                    let x = it.next().unwrap();
-                    let y = it.next().unwrap();
-                    Coord::new(x, y)
-                }).collect()
-            }).collect();
-        let start = Coord::new(500, 0);
-        let it = iter::once(&start).chain(rocks.iter().flatten());
-        let mut y_sorted = it.clone().sorted_by(|a, b| a.y.cmp(&b.y));
-        let mut x_sorted = it.sorted_by(|a, b| a.x.cmp(&b.x));
-        let offset_y = y_sorted.next().unwrap().y;
-        let max_y = y_sorted.last().unwrap().y;        
-        let offset_x = x_sorted.next().unwrap().x - 1;
-        let max_x = x_sorted.last().unwrap().x + 1;        
-        let mut map: Vec<Vec<Material>> = (0..=max_y-offset_y).map(|_| (0..=max_x-offset_x).map(|_|Material::Air).collect()).collect();
-
-        rocks.iter_mut().flatten().for_each(|c| {
-            c.x -= offset_x;
-            c.y -= offset_y;
-        });
-
-        for rock_formation in rocks {
-            let mut it = rock_formation.iter().peekable();
-            'rock_path: while let Some(rock_start) = it.next() {
-                let rock_end = it.peek();
-                let Some(rock_end) = rock_end else {
-                    break 'rock_path;
-                };
-                if rock_start.x == rock_end.x {
-                    // Vertical
-                    let c_min_y = cmp::min(rock_start.y, rock_end.y);
-                    let c_max_y = cmp::max(rock_start.y, rock_end.y);
-                    for y in c_min_y..=c_max_y {
-                        let v = &mut map[y][rock_start.x];
-                        *v = Material::Rock;
-                    }
-                } else {
-                    // Horizontal
-                    let c_min_x = cmp::min(rock_start.x, rock_end.x);
-                    let c_max_x = cmp::max(rock_start.x, rock_end.x);
-                    for x in c_min_x..=c_max_x {
-                        let v = &mut map[rock_start.y][x];
-                        *v = Material::Rock;
-                    }
-                }
-            }
-        }
-        let grid = Grid::new(map);
-        RegolithReservoir { 
-            grid,
-            offset_x,
+    fn new(data: &str, params: &crate::Params) -> Self {
+        RegolithReservoir {
+            rocks: parse_rocks(data).unwrap(),
+            source: Coord::new(params.get("source_x"), params.get("source_y")),
+            columns: params.get("columns"),
+            frame_every: params.get("frame_every")
         }
     }
 
-    fn part_01(&self) -> String {        
-        let start = Coord::new(500 - self.offset_x, 0);
-        let mut grid = self.grid.clone();
+    fn parameters() -> &'static [crate::Parameter] {
+        const PARAMETERS: &[crate::Parameter] = &[
+            crate::Parameter::new::<i32>("source_x", "500", "500"),
+            crate::Parameter::new::<i32>("source_y", "0", "0"),
+            crate::Parameter::new::<bool>("columns", "false", "false"),
+            crate::Parameter::new::<usize>("frame_every", "0", "0"),
+        ];
+        PARAMETERS
+    }
 
-        let sand_count = pour_sand(&mut grid, start);
-        sand_count.to_string()
+    fn part_01(&self) -> String {
+        self.pour(Bottom::Abyss).to_string()
     }
 
     fn part_02(&self) -> String {
-        // Plus four becouse of 2 extra rows we'll be adding, and one one each side for overflow        
-        let desired_width = (self.grid.height + 4) * 3; 
-        let to_add = (desired_width - self.grid.width) / 2;
-        let width = to_add * 2 + self.grid.width;
-        
-        let two_rows: Vec<Vec<Material>> = (0..2).map(|y| {
-            (0..width).map(|_| {
-                if y == 1{
-                    Material::Rock
-                } else {
-                    Material::Air
-                }
-            }).collect()
-        }).collect();
-
-        let map: Vec<Vec<Material>> = self.grid.map
-            .chunks(self.grid.width)
-            .map(|chunk| {
-                let lhs_add: Vec<Material> = (0..to_add).map(|_| Material::Air).collect();
-                let rhs_add: Vec<Material> = (to_add + self.grid.width..2 * to_add + self.grid.width).map(|_| Material::Air).collect();
-                lhs_add.into_iter().chain(chunk.iter().cloned()).chain(rhs_add.into_iter()).collect()            
-            })
-            .chain(two_rows.iter().cloned())
-            .collect();
-
-        let start = Coord::new(500 - self.offset_x + to_add, 0);
-        let mut grid = Grid::new(map);
-        let sand_count = pour_sand(&mut grid, start);
-        sand_count.to_string()
+        self.pour(Bottom::Floor).to_string()
     }
 }
 
-#[allow(dead_code)]
-fn print_grid(grid: &Grid<Material>, path: Vec<C>) {        
-    grid.display_with_points(path.clone(), '~');
-    wait_user_input();
-    print!("{esc}c", esc = 27 as char);
+impl RegolithReservoir {
+    /// Pours sand until it stops, returns how many grains came to rest.
+    fn pour(&self, bottom: Bottom) -> usize {
+        let mut engine = SandEngine::new(self.rocks.clone(), self.source, bottom);
+        if self.frame_every > 0 {
+            for frame in engine.frames(self.frame_every) {
+                println!("{}", frame);
+            }
+        }
+        // Whatever the frames haven't dropped yet
+        engine.by_ref().for_each(drop);
+        let columns = engine.resting_per_column();
+        if self.columns {
+            for (x, count) in columns.iter() {
+                println!("Column {}: {} sand", x, count);
+            }
+        }
+        columns.values().sum()
+    }
 }
 
+/// Parses rock paths like `498,4 -> 498,6 -> 496,6`, every segment has to be
+/// horizontal or vertical.
+fn parse_rocks(data: &str) -> Result<HashMap<C, Material>> {
+    let paths = parse::lines(data, |l| {
+        let points = l.split(" -> ").map(|point| {
+            let [x, y] = parse::template("{},{}", point)?;
+            Ok(Coord::new(parse::value(x)?, parse::value(y)?))
+        }).collect::<Result<Vec<C>>>()?;
+        for segment in points.windows(2) {
+            let (from, to) = (segment[0], segment[1]);
+            if from.x != to.x && from.y != to.y {
+                bail!("Segment {},{} -> {},{} is diagonal", from.x, from.y, to.x, to.y);
+            }
+        }
+        Ok(points)
+    })?;
 
-fn pour_sand(grid: &mut Grid<Material>, start: C) -> usize {
-    let mut sand_count = 0;
-    let mut current_path = vec![start];
-    'pour: loop {
-        if let Some(current) = current_path.last() {
-            // print_grid(grid, current_path.clone());
-            if let Some((rest_position, mut new_path_taken)) = search_next_rest_position(grid, &current) {
-                current_path.append(&mut new_path_taken);
-                sand_count += 1;
-                *grid.get_val_mut(&rest_position) = Material::Sand;                 
-                current_path.pop();                
-            } else {                
-                // println!("Reached infinity...");
-                break 'pour;
-            }            
-        } else {
-            // println!("Reached start coordinate...");
-            break 'pour;
+    let mut rocks = HashMap::new();
+    for path in paths {
+        rocks.insert(path[0], Material::Rock);
+        for segment in path.windows(2) {
+            let (from, to) = (segment[0], segment[1]);
+            for x in from.x.min(to.x)..=from.x.max(to.x) {
+                for y in from.y.min(to.y)..=from.y.max(to.y) {
+                    rocks.insert(Coord::new(x, y), Material::Rock);
+                }
+            }
         }
     }
-    sand_count
+    Ok(rocks)
+}
+
+/// What is under the lowest rock.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Bottom {
+    /// Sand falls forever
+    Abyss,
+    /// Endless floor two rows below the lowest rock
+    Floor
 }
 
-fn search_next_rest_position(grid: &Grid<Material>, start: &C) -> Option<(C, Vec<C>)> {
-    let mut path: Vec<C> = vec![];
-    let mut current = *start;
-    'search_for_rest: loop {
-        let next_coord = check_next_step(grid, current);
-        match next_coord {
-            NextCoord::Empty(c) => {
-                path.push(c);
-                current = c;
-                continue 'search_for_rest;
-            },
-            NextCoord::Full => {
-                return Some((current, path));
-            },
-            NextCoord::Infinity => {
+/// Drops one grain of sand at a time from the source, yielding where every
+/// grain comes to rest.
+struct SandEngine {
+    cells: HashMap<C, Material>,
+    source: C,
+    bottom: Bottom,
+    lowest_rock: i32,
+    // Path of the last grain, the next one falls the same way until the
+    // first cell that is now taken
+    path: Vec<C>
+}
+
+impl SandEngine {
+    fn new(rocks: HashMap<C, Material>, source: C, bottom: Bottom) -> Self {
+        let lowest_rock = rocks.keys().map(|c| c.y).max().unwrap_or(source.y);
+        let path = if rocks.contains_key(&source) { vec![] } else { vec![source] };
+        SandEngine { cells: rocks, source, bottom, lowest_rock, path }
+    }
+
+    fn floor(&self) -> Option<i32> {
+        match self.bottom {
+            Bottom::Abyss => None,
+            Bottom::Floor => Some(self.lowest_rock + 2)
+        }
+    }
+
+    fn is_free(&self, c: &C) -> bool {
+        !self.cells.contains_key(c) && self.floor().is_none_or(|floor| c.y < floor)
+    }
+
+    fn drop_grain(&mut self) -> Option<C> {
+        loop {
+            let current = *self.path.last()?;
+            if self.bottom == Bottom::Abyss && current.y >= self.lowest_rock {
+                // Nothing below stops the sand anymore
+                self.path.clear();
                 return None;
             }
+            let next = [0, -1, 1].iter()
+                .map(|dx| Coord::new(current.x + dx, current.y + 1))
+                .find(|c| self.is_free(c));
+            match next {
+                Some(next) => self.path.push(next),
+                None => {
+                    self.cells.insert(current, Material::Sand);
+                    self.path.pop();
+                    return Some(current);
+                }
+            }
         }
     }
+
+    /// Renders the cave after every `grains` grains until the sand stops.
+    fn frames(&mut self, grains: usize) -> impl Iterator<Item = String> + '_ {
+        std::iter::from_fn(move || {
+            let dropped = self.by_ref().take(grains).count();
+            (dropped > 0).then(|| self.to_string())
+        })
+    }
+
+    fn resting_per_column(&self) -> BTreeMap<i32, usize> {
+        let mut columns = BTreeMap::new();
+        for (c, material) in self.cells.iter() {
+            if *material == Material::Sand {
+                *columns.entry(c.x).or_default() += 1;
+            }
+        }
+        columns
+    }
 }
 
+impl Iterator for SandEngine {
+    type Item = C;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.drop_grain()
+    }
+}
 
-#[derive(Debug)]
-enum NextCoord {
-    Empty(C),
-    Full,
-    Infinity
+impl fmt::Display for SandEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let coords = self.cells.keys().chain(std::iter::once(&self.source));
+        let min_x = coords.clone().map(|c| c.x).min().unwrap();
+        let max_x = coords.clone().map(|c| c.x).max().unwrap();
+        let min_y = coords.clone().map(|c| c.y).min().unwrap();
+        let max_y = coords.map(|c| c.y).max().unwrap().max(self.floor().unwrap_or(i32::MIN));
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let c = Coord::new(x, y);
+                match self.cells.get(&c) {
+                    Some(material) => write!(f, "{}", material)?,
+                    None if Some(y) == self.floor() => write!(f, "{}", Material::Rock)?,
+                    None if c == self.source => write!(f, "+")?,
+                    None => write!(f, "{}", Material::Air)?
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
 }
 
-fn check_next_step(grid: &Grid<Material>, current: C) -> NextCoord {
-    let down_coord = Coord::new(current.x, current.y + 1);
-    if down_coord.y == grid.height {
-        return NextCoord::Infinity;
-    }    
-    if grid.get_val(&down_coord).is_empty() {
-        return NextCoord::Empty(down_coord);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example(bottom: Bottom) -> SandEngine {
+        let rocks = parse_rocks(include_str!("example.txt")).unwrap();
+        SandEngine::new(rocks, Coord::new(500, 0), bottom)
+    }
+
+    #[test]
+    fn test_example() {
+        assert_eq!(example(Bottom::Abyss).count(), 24);
+        let mut engine = example(Bottom::Floor);
+        assert_eq!(engine.by_ref().count(), 93);
+        assert_eq!(engine.resting_per_column().values().sum::<usize>(), 93);
+        assert_eq!(engine.resting_per_column()[&500], 9);
     }
 
-    let left_coord = Coord::new(down_coord.x - 1, down_coord.y);
-    if grid.get_val(&left_coord).is_empty() {
-        return NextCoord::Empty(left_coord);
+    #[test]
+    fn test_frames() {
+        let mut engine = example(Bottom::Abyss);
+        let mut frames = engine.frames(1);
+        assert_eq!(frames.next().unwrap(), "\
+......+...
+..........
+..........
+..........
+....#...##
+....#...#.
+..###...#.
+........#.
+......O.#.
+#########.
+");
+        assert_eq!(frames.count(), 23);
     }
 
-    let right_coord = Coord::new(down_coord.x + 1, down_coord.y);
-    if grid.get_val(&right_coord).is_empty() {
-        return NextCoord::Empty(right_coord);
+    #[test]
+    fn test_source() {
+        // A source sitting on a single rock piles sand up on both sides
+        let rocks = parse_rocks("10,5").unwrap();
+        let mut engine = SandEngine::new(rocks, Coord::new(10, 4), Bottom::Floor);
+        assert_eq!(engine.by_ref().count(), 1 + 2 + 5);
+        assert!(SandEngine::new(parse_rocks("10,5").unwrap(), Coord::new(10, 4), Bottom::Abyss).next().is_none());
     }
-    NextCoord::Full
-}
\ No newline at end of file
+
+    #[test]
+    fn test_bad_segments() {
+        let error = parse_rocks("498,4 -> 498,6\n498,6 -> 496,8").unwrap_err();
+        assert!(format!("{:#}", error).starts_with("Line 2: "));
+        assert!(format!("{:#}", error).ends_with("Segment 498,6 -> 496,8 is diagonal"));
+        assert!(parse_rocks("498,4 -> 498").is_err());
+        assert!(parse_rocks("498,4 -> a,6").is_err());
+    }
+}
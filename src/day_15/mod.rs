@@ -1,7 +1,8 @@
-use std::{ops::Range};
 use std::str::FromStr;
-use anyhow::{Error, Context};
+use anyhow::{Error, anyhow};
 use crate::utils::point::Coord;
+use crate::utils::intervals::RangeSet;
+use crate::utils::parse;
 
 type C = Coord<i64>;
 
@@ -15,17 +16,9 @@ impl FromStr for Sensor {
     type Err = Error;
     
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (lhs, rhs) = s.split_once(": ").context("Invalid Sensor string")?;
-        let lhs: Vec<i64> = lhs.strip_prefix("Sensor at ").context("Invalid sensor string")?.split(", ").map(|part| {
-            let value = part.split("=").last().context("No '=' sign to split on.")?;
-            value.parse().context("Value couldn't be parsed to integer")
-        }).collect::<Result<_, _>>()?;
-        let rhs: Vec<i64> = rhs.strip_prefix("closest beacon is at ").context("Invalid sensor string")?.split(", ").map(|part| {
-            let value = part.split("=").last().context("No '=' sign to split on.")?;
-            value.parse().context("Value couldn't be parsed to integer")
-        }).collect::<Result<_, _>>()?;
-        let sensor = Coord::new(lhs[0], lhs[1]);
-        let beacon = Coord::new(rhs[0], rhs[1]);
+        let [sx, sy, bx, by] = parse::template("Sensor at x={}, y={}: closest beacon is at x={}, y={}", s)?;
+        let sensor = Coord::new(parse::value(sx)?, parse::value(sy)?);
+        let beacon = Coord::new(parse::value(bx)?, parse::value(by)?);
         Ok(Sensor::new(sensor, beacon))
     }
 }
@@ -40,6 +33,12 @@ impl Sensor {
         other.manhattan_distance(&self.coord) <= self.distance
     }
 
+    /// Whether the sensor sees any point of the box.
+    fn touches(&self, min: C, max: C) -> bool {
+        let closest = Coord::new(self.coord.x.clamp(min.x, max.x), self.coord.y.clamp(min.y, max.y));
+        self.within_sensor_range(&closest)
+    }
+
     fn can_contain_unseen_points(&self, min: C, max: C) -> bool {
         let corners = [
             Coord::new(min.x, min.y),
@@ -54,127 +53,221 @@ impl Sensor {
     }
 }
 
-pub struct BeaconExclusionZone {
+/// How part 2 looks for the distress beacon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SearchMethod {
+    /// Subdivides the search area, dropping quadrants a sensor sees whole
+    Quadtree,
+    /// Intersects the lines just outside of the sensor ranges
+    Lines
+}
+
+impl FromStr for SearchMethod {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "quadtree" => Ok(SearchMethod::Quadtree),
+            "lines" => Ok(SearchMethod::Lines),
+            _ => Err(anyhow!("Invalid search method {}, expected quadtree or lines", s))
+        }
+    }
+}
+
+/// Area covered by the sensors, every sensor sees a diamond reaching up to
+/// its closest beacon.
+struct SensorField {
     sensors: Vec<Sensor>
 }
 
+impl SensorField {
+    fn new(sensors: Vec<Sensor>) -> Self {
+        SensorField { sensors }
+    }
+
+    /// Columns seen by any sensor at row `y`.
+    fn coverage(&self, y: i64) -> RangeSet<i64> {
+        self.sensors.iter().filter_map(|s| {
+            let y_diff = s.coord.y.abs_diff(y) as i64;
+            if y_diff > s.distance {
+                return None;
+            }
+            let x_part = s.distance - y_diff;
+            Some(s.coord.x - x_part..=s.coord.x + x_part)
+        }).collect()
+    }
+
+    /// Positions at row `y` that can't hold a beacon, known beacons and the
+    /// sensors themselves don't count.
+    fn beacon_free(&self, y: i64) -> i64 {
+        let covered = self.coverage(y);
+        let mut occupied: Vec<i64> = self.sensors.iter()
+            .flat_map(|s| [s.beacon, s.coord])
+            .filter(|o| o.y == y)
+            .map(|o| o.x)
+            .collect();
+        occupied.sort_unstable();
+        occupied.dedup();
+        covered.len() - occupied.iter().filter(|x| covered.contains(x)).count() as i64
+    }
+
+    /// Boxes within `min..=max` that no sensor sees, as `(min, max)` corners.
+    fn uncovered_regions(&self, min: C, max: C) -> Vec<(C, C)> {
+        let mut regions = vec![];
+        let mut quadrant_stack = vec![(min, max)];
+        while let Some((min, max)) = quadrant_stack.pop() {
+            if min.x > max.x || min.y > max.y {
+                continue;
+            }
+            if self.sensors.iter().any(|s| !s.can_contain_unseen_points(min, max)) {
+                continue;
+            }
+            if self.sensors.iter().all(|s| !s.touches(min, max)) {
+                regions.push((min, max));
+                continue;
+            }
+            let mid = Coord::new((min.x + max.x).div_euclid(2), (min.y + max.y).div_euclid(2));
+            quadrant_stack.extend([
+                (min, mid),
+                (Coord::new(mid.x + 1, min.y), Coord::new(max.x, mid.y)),
+                (Coord::new(min.x, mid.y + 1), Coord::new(mid.x, max.y)),
+                (Coord::new(mid.x + 1, mid.y + 1), max),
+            ]);
+        }
+        regions
+    }
+
+    /// Every point within `min..=max` that no sensor sees.
+    fn uncovered_points(&self, min: C, max: C) -> impl Iterator<Item = C> {
+        self.uncovered_regions(min, max).into_iter().flat_map(|(min, max)| {
+            (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| Coord::new(x, y)))
+        })
+    }
+
+    /// A single unseen point has to be right outside of the ranges of
+    /// several sensors, so it lies where their edges cross. In rotated
+    /// coordinates `u = x + y` and `v = x - y` the edges are straight
+    /// lines, which makes crossings cheap to find. On the border of the box
+    /// the point only needs edges of one direction, so the spots where the
+    /// lines meet the border are checked too, along with the corners.
+    fn unseen_point_by_lines(&self, min: C, max: C) -> Option<C> {
+        let mut us = vec![];
+        let mut vs = vec![];
+        for s in self.sensors.iter() {
+            let (u, v) = (s.coord.x + s.coord.y, s.coord.x - s.coord.y);
+            us.extend([u - s.distance - 1, u + s.distance + 1]);
+            vs.extend([v - s.distance - 1, v + s.distance + 1]);
+        }
+        let crossings = us.iter().flat_map(|u| vs.iter().map(move |v| (*u, *v)))
+            .filter(|(u, v)| (u - v).rem_euclid(2) == 0)
+            .map(|(u, v)| Coord::new((u + v) / 2, (u - v) / 2));
+        let corners = [min, Coord::new(min.x, max.y), Coord::new(max.x, min.y), max];
+        let border = us.iter().flat_map(|u| [
+            Coord::new(min.x, u - min.x), Coord::new(max.x, u - max.x),
+            Coord::new(u - min.y, min.y), Coord::new(u - max.y, max.y)
+        ]).chain(vs.iter().flat_map(|v| [
+            Coord::new(min.x, min.x - v), Coord::new(max.x, max.x - v),
+            Coord::new(v + min.y, min.y), Coord::new(v + max.y, max.y)
+        ]));
+        corners.into_iter().chain(crossings).chain(border).find(|c| {
+            (min.x..=max.x).contains(&c.x)
+                && (min.y..=max.y).contains(&c.y)
+                && self.sensors.iter().all(|s| !s.within_sensor_range(c))
+        })
+    }
+
+    fn unseen_point(&self, min: C, max: C, method: SearchMethod) -> Option<C> {
+        match method {
+            SearchMethod::Quadtree => self.uncovered_points(min, max).next(),
+            SearchMethod::Lines => self.unseen_point_by_lines(min, max)
+        }
+    }
+}
+
+pub struct BeaconExclusionZone {
+    field: SensorField,
+    row: i64,
+    search_limit: i64,
+    search_method: SearchMethod
+}
+
 impl crate::Advent for BeaconExclusionZone {
-    fn new(data: &str) -> Self
+    fn new(data: &str, params: &crate::Params) -> Self
     where 
     Self: Sized 
     {
-        let sensors: Vec<Sensor> = data.lines().map(|l| {
-            Sensor::from_str(l).unwrap()
-        }).collect();  
-        BeaconExclusionZone { sensors }
+        let sensors: Vec<Sensor> = parse::lines(data, Sensor::from_str).unwrap();
+        BeaconExclusionZone {
+            field: SensorField::new(sensors),
+            row: params.get("row"),
+            search_limit: params.get("search_limit"),
+            search_method: params.get("search_method")
+        }
     }
+
+    fn parameters() -> &'static [crate::Parameter] {
+        const PARAMETERS: &[crate::Parameter] = &[
+            crate::Parameter::new::<i64>("row", "2000000", "10"),
+            crate::Parameter::new::<i64>("search_limit", "4000000", "20"),
+            crate::Parameter::new::<SearchMethod>("search_method", "lines", "lines"),
+        ];
+        PARAMETERS
+    }
+
     fn part_01(&self) -> String {
-        let y = 2_000_000;
-        // let y = 10; // For example input
-        let lower_limit_x = self.sensors.iter().map(|s| s.coord.x - s.distance ).min().unwrap();
-        let upper_limit_x = self.sensors.iter().map(|s| s.coord.x + s.distance ).max().unwrap();
-        let full_ranges = beacon_covered_at_y(&self.sensors, y, lower_limit_x, upper_limit_x);
-        let mut to_exclude: Vec<i64> = self.sensors.iter().map(|s| {
-            vec![s.beacon, s.coord]
-        }).flatten().filter_map(|o| {
-            if o.y == y {
-                Some(o.x)
-            } else {
-                None
-            }
-        }).collect();
-        to_exclude.sort_unstable();
-        to_exclude.dedup();
-
-        let result = full_ranges.iter().map(|range| {
-            let mut len = range.end - range.start;
-            for exclude in to_exclude.iter() {
-                if range.contains(exclude) {
-                    len -= 1;
-                }
-            }
-            len
-        }).sum::<i64>();
-        result.to_string()
+        self.field.beacon_free(self.row).to_string()
     }
 
     fn part_02(&self) -> String {
-        let lower_limit = 0;
-        let upper_limit = 4_000_000;
-        // let upper_limit = 20; // For example input
-        let min = Coord::new(lower_limit, lower_limit);
-        let max = Coord::new(upper_limit, upper_limit);
-        let empty_space = find_useen_point(&self.sensors, min, max).unwrap();
+        let min = Coord::new(0, 0);
+        let max = Coord::new(self.search_limit, self.search_limit);
+        let empty_space = self.field.unseen_point(min, max, self.search_method).unwrap();
         let result = (empty_space.x * 4_000_000) + empty_space.y;
         result.to_string()
     }
 }
 
-fn beacon_covered_at_y(sensors: &Vec<Sensor>, y: i64, lower_limit_x: i64, upper_limit_x: i64) -> Vec<Range<i64>> {
-    let mut sensors: Vec<_> = sensors.iter().filter_map(|s| {
-        let y_diff = s.coord.y.abs_diff(y) as i64;
-        if y_diff > s.distance {
-            return None;
-        }
-        let x_part = s.distance - y_diff;
-        let min_x = s.coord.x - x_part;
-        let max_x = s.coord.x + x_part;
-        Some((min_x, max_x))
-    }).collect();
-
-    sensors.sort_by(|lhs, rhs| {
-        lhs.0.cmp(&rhs.0)
-    });
-
-    let mut ranges: Vec<Range<i64>>= vec![];
-    let mut current_x = lower_limit_x;
-    for (min_x, max_x) in sensors.iter() {        
-        let start = i64::max(*min_x, current_x);                
-        let end = i64::min(*max_x, upper_limit_x);
-        if end < current_x || start > upper_limit_x {
-            continue;
-        }
-        ranges.push(start..end + 1);
-        current_x = end + 1;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> SensorField {
+        SensorField::new(parse::lines(include_str!("example.txt"), Sensor::from_str).unwrap())
     }
-    ranges
-}
 
-fn find_useen_point(
-    sensors: &Vec<Sensor>,
-    min: C,
-    max: C
-) -> Option<C> {
-    let mut quadrant_stack = vec![(min, max)];
-    while let Some((min, max)) = quadrant_stack.pop() {
-        if min == max {
-            if sensors.iter().all(|pair| {
-                !pair.within_sensor_range(&min)
-            }) {
-                return Some(min);
-            }
-        } else {
-            let mid = Coord::new((min.x + max.x) / 2, (min.y + max.y) / 2);
-            let quadrants = [
-                (min, mid),
-                (Coord::new(mid.x + 1, min.y), Coord::new(max.x, mid.y)),
-                (Coord::new(min.x, mid.y + 1), Coord::new(mid.x, max.y)),
-                (Coord::new(mid.x + 1, mid.y + 1), max),
-            ];
-            for quadrant in quadrants {
-                if quadrant.0.x > quadrant.1.x || quadrant.0.y > quadrant.1.y {
-                    continue;
-                }
-
-                if sensors
-                    .iter()
-                    .all(|pair| {
-                        pair.can_contain_unseen_points(quadrant.0, quadrant.1)
-                    }) {
-                    quadrant_stack.push(quadrant);
-                }
-            }
-        }
+    #[test]
+    fn test_rows() {
+        let field = example();
+        assert_eq!(field.beacon_free(10), 26);
+        let gaps = field.coverage(11).gaps_within(0..=20);
+        assert_eq!((gaps.min(), gaps.max(), gaps.len()), (Some(14), Some(14), 1));
+        let covered = field.coverage(10);
+        assert_eq!((covered.min(), covered.max(), covered.ranges().len()), (Some(-2), Some(24), 1));
+    }
+
+    #[test]
+    fn test_unseen() {
+        let field = example();
+        let (min, max) = (Coord::new(0, 0), Coord::new(20, 20));
+        assert_eq!(field.uncovered_points(min, max).collect::<Vec<_>>(), vec![Coord::new(14, 11)]);
+        assert_eq!(field.unseen_point(min, max, SearchMethod::Quadtree), Some(Coord::new(14, 11)));
+        assert_eq!(field.unseen_point(min, max, SearchMethod::Lines), Some(Coord::new(14, 11)));
+        // Far from the sensors everything is unseen
+        let far = field.uncovered_regions(Coord::new(100, 100), Coord::new(103, 101));
+        assert_eq!(far, vec![(Coord::new(100, 100), Coord::new(103, 101))]);
+        assert_eq!(field.uncovered_points(Coord::new(100, 100), Coord::new(103, 101)).count(), 8);
+    }
+
+    #[test]
+    fn test_unseen_on_border() {
+        // (2, 0) is only bounded by edges running the same way
+        let field = SensorField::new(parse::lines("\
+Sensor at x=0, y=2: closest beacon is at x=3, y=2
+Sensor at x=4, y=-1: closest beacon is at x=4, y=1
+Sensor at x=4, y=4: closest beacon is at x=0, y=4", Sensor::from_str).unwrap());
+        let (min, max) = (Coord::new(0, 0), Coord::new(4, 4));
+        assert_eq!(field.unseen_point(min, max, SearchMethod::Quadtree), Some(Coord::new(2, 0)));
+        assert_eq!(field.unseen_point(min, max, SearchMethod::Lines), Some(Coord::new(2, 0)));
     }
-    None
-}
\ No newline at end of file
+}
@@ -0,0 +1,191 @@
+// My implementation of this monster of a problem
+// Code works, but for part 2 takes too long with real input
+// (for example it works fine).
+
+use std::collections::{HashMap, VecDeque};
+use anyhow::{Error, Context};
+use itertools::Itertools;
+use std::str::FromStr;
+
+pub struct ProboscideaVolcanium {
+    map: HashMap<String, Valve>,
+    minutes_part_01: i32,
+    minutes_part_02: i32
+}
+
+#[derive(Debug, Clone)]
+struct Valve {
+    name: String,
+    flow_rate: i32,
+    leads_to: Vec<String>
+}
+
+impl FromStr for Valve {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (lhs, rhs) = s.split_once("; ").context("Invalid valve string")?;
+        let lhs = lhs.strip_prefix("Valve ").context("Invalid valve string")?;
+        let (name, rest) = lhs.split_at(2);
+        let val = rest.strip_prefix(" has flow rate=").context("Invalid valve string")?.parse().context("Can't parse value to integer")?;
+        let leads_to = match rhs.strip_prefix("tunnels lead to valves ") {
+            Some(rhs) => rhs.split(", ").map(|s| s.to_owned()).collect(),
+            None => {
+                let single_valve = rhs.strip_prefix("tunnel leads to valve ").context("Invalid valve string")?;
+                vec![single_valve.to_owned()]
+            }
+        };
+        Ok(Valve {
+            name: name.to_string(),
+            flow_rate: val,
+            leads_to
+        })
+    }
+}
+
+impl crate::Advent for ProboscideaVolcanium {
+    fn new(data: &str, params: &crate::Params) -> Self
+        where
+            Self: Sized {
+        let map: HashMap<String, Valve> = data.lines().map(|l| {
+            let valve = Valve::from_str(l).unwrap();
+            (valve.name.clone(), valve)
+        }).collect();
+
+        ProboscideaVolcanium {
+            map,
+            minutes_part_01: params.get("minutes_part_01"),
+            minutes_part_02: params.get("minutes_part_02")
+        }
+    }
+
+    fn parameters() -> &'static [crate::Parameter] {
+        const PARAMETERS: &[crate::Parameter] = &[
+            crate::Parameter::new::<i32>("minutes_part_01", "30", "30"),
+            crate::Parameter::new::<i32>("minutes_part_02", "26", "26"),
+        ];
+        PARAMETERS
+    }
+
+    fn part_01(&self) -> String {
+        self.get_max_pressure(self.minutes_part_01).to_string()
+    }
+
+    fn part_02(&self) -> String {
+        self.get_max_pressure_double(self.minutes_part_02).to_string()
+    }
+}
+
+impl ProboscideaVolcanium {
+    fn distances(&self) -> HashMap<&str, HashMap<&str, i32>> {
+        self.map.keys().map(|vertex| (vertex.as_str(), self.get_distances(vertex))).collect()
+    }
+
+    fn get_max_pressure(&self, time_limit: i32) -> i32 {
+        // cost = (sum_pressure, time_passed)
+        let mut queue: VecDeque<(&str, (i32, i32), Vec<&str>)> = VecDeque::new();
+        queue.push_back(("AA", (0, 0), vec![]));
+        let distances_map = self.distances();
+        let vertices: Vec<&str> = self.map.keys().map(String::as_str).sorted().collect();
+        let mut node_cost_map: HashMap<&str, (i32, i32)> = vertices.iter()
+            .map(|v| (*v, (i32::MIN, i32::MAX)))
+            .collect();
+
+        let mut max_pressure = i32::MIN;
+        while let Some((u, cost, visited)) = queue.pop_front() {
+            max_pressure = max_pressure.max(cost.0);
+            let distance_map = &distances_map[u];
+            for vertex in vertices.iter() {
+                if visited.contains(vertex) {
+                    continue;
+                }
+                let (pressure_release, time_to_release_pressure) = self.get_pressure_and_time(vertex, distance_map[vertex], cost.1, time_limit);
+                if time_to_release_pressure > time_limit {
+                    continue;
+                }
+                let new_cost = (cost.0 + pressure_release, time_to_release_pressure);
+                let current_cost = node_cost_map.get_mut(vertex).unwrap();
+                if new_cost.0 + pressure_release >= current_cost.0 {
+                    *current_cost = new_cost;
+                    let mut new_visited = visited.clone();
+                    new_visited.push(vertex);
+                    queue.push_back((vertex, new_cost, new_visited));
+                }
+            }
+        }
+        max_pressure
+    }
+
+    fn get_max_pressure_double(&self, time_limit: i32) -> i32 {
+        // cost = (sum_pressure, time_passed) for me and for the elephant
+        type Walker<'a> = (&'a str, (i32, i32));
+        let mut queue: VecDeque<(Walker, Walker, Vec<&str>)> = VecDeque::new();
+        queue.push_back((("AA", (0, 0)), ("AA", (0, 0)), vec![]));
+        let distances_map = self.distances();
+        let vertices: Vec<&str> = self.map.keys().map(String::as_str).sorted().collect();
+        let vertices_perm: Vec<(&str, &str)> = vertices.iter()
+            .permutations(2)
+            .map(|vs| (*vs[0], *vs[1]))
+            .collect();
+        let mut node_cost_map: HashMap<(&str, &str), (i32, i32)> = vertices_perm.iter()
+            .map(|pair| (*pair, (i32::MIN, i32::MAX)))
+            .collect();
+
+        let mut max_pressure = i32::MIN;
+        while let Some(((u, u_cost), (i, i_cost), visited)) = queue.pop_back() {
+            max_pressure = max_pressure.max(u_cost.0 + i_cost.0);
+            let u_distance_map = &distances_map[u];
+            let i_distance_map = &distances_map[i];
+            for (u_vertex, i_vertex) in vertices_perm.iter() {
+                if visited.contains(u_vertex) || visited.contains(i_vertex) {
+                    continue;
+                }
+                let u_pt = self.get_pressure_and_time(u_vertex, u_distance_map[u_vertex], u_cost.1, time_limit);
+                let i_pt = self.get_pressure_and_time(i_vertex, i_distance_map[i_vertex], i_cost.1, time_limit);
+                if u_pt.1 > time_limit || i_pt.1 > time_limit {
+                    continue;
+                }
+                let u_new_cost = (u_cost.0 + u_pt.0, u_pt.1);
+                let i_new_cost = (i_cost.0 + i_pt.0, i_pt.1);
+
+                let current_cost = node_cost_map.get_mut(&(*u_vertex, *i_vertex)).unwrap();
+                if (u_new_cost.0 + u_pt.0 + i_new_cost.0 + i_pt.0) >= current_cost.0 {
+                    *current_cost = (u_new_cost.0 + i_new_cost.0, u_new_cost.1.max(i_new_cost.1));
+                    let mut new_visited = visited.clone();
+                    new_visited.push(u_vertex);
+                    new_visited.push(i_vertex);
+                    queue.push_back(((u_vertex, u_new_cost), (i_vertex, i_new_cost), new_visited));
+                }
+            }
+        }
+        max_pressure
+    }
+
+    fn get_pressure_and_time(&self, node: &str, distance: i32, time_passed: i32, time_limit: i32) -> (i32, i32) {
+        let time_to_release_pressure = time_passed + distance + 1;
+        let pressure_release = (time_limit - time_to_release_pressure) * self.map[node].flow_rate;
+        (pressure_release, time_to_release_pressure)
+    }
+
+    fn get_distances(&self, start: &str) -> HashMap<&str, i32> {
+        let mut queue: Vec<&str> = self.map.keys().map(String::as_str).collect();
+        let mut distance_map: HashMap<&str, i32> = queue.iter().map(|k| (*k, i32::MAX)).collect();
+        *distance_map.get_mut(start).unwrap() = 0;
+
+        while !queue.is_empty() {
+            let (pos, name) = queue.iter()
+                .copied()
+                .enumerate()
+                .min_by_key(|(_, k)| distance_map[k])
+                .unwrap();
+            let distance = distance_map[name].saturating_add(1);
+            queue.remove(pos);
+
+            for neighbour in self.map[name].leads_to.iter() {
+                if queue.contains(&neighbour.as_str()) && distance < distance_map[neighbour.as_str()] {
+                    *distance_map.get_mut(neighbour.as_str()).unwrap() = distance;
+                }
+            }
+        }
+        distance_map
+    }
+}
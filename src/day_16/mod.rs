@@ -2,14 +2,44 @@
 // Copied code from: https://github.com/Crazytieguy/advent-of-code/blob/master/2022/src/bin/day16/main.rs
 // My solution doesn't run this fast xD
 
+pub mod longest_path;
+
 use std::{collections::HashMap, cmp::Reverse};
+use std::str::FromStr;
 
 use itertools::Itertools;
+use anyhow::{Context, Error, Result, anyhow, bail};
+use crate::utils::parse;
 
 type Valve<'a> = (&'a str, u8, Vec<&'a str>);
 type FlowRates = Vec<u8>;
 type FlowRateIndices = Vec<usize>;
 type ShortesPathLenghts = Vec<Vec<u8>>;
+/// Set of valves as a bit mask over the interesting valve indices.
+type Visited = u64;
+
+/// How the elephant search of part 2 prunes its branches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Strategy {
+    /// Explores every path without a bound, guaranteed to find the optimum.
+    /// Takes 20-45ms on my input, about ten times the heuristic
+    Exact,
+    /// Drops branches whose bound is below 3/4 of the best pressure found so
+    /// far, much faster but could miss the optimum
+    Heuristic
+}
+
+impl FromStr for Strategy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "exact" => Ok(Strategy::Exact),
+            "heuristic" => Ok(Strategy::Heuristic),
+            _ => Err(anyhow!("Invalid strategy {}, expected exact or heuristic", s))
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct ProboscideaVolcanium {
@@ -17,23 +47,23 @@ pub struct ProboscideaVolcanium {
     flow_rates: FlowRates,
     shortest_path_lengths: ShortesPathLenghts,
     flow_rate_indices: FlowRateIndices,
-    starting_node: usize
+    starting_node: usize,
+    minutes_part_01: u8,
+    minutes_part_02: u8,
+    strategy_part_02: Strategy
 }
 
-impl crate::Advent for ProboscideaVolcanium {
-    fn new(data: &str) -> Self {
-        let valves: Vec<Valve> = data.lines().map(|l| {
-            let (lhs, rhs) = l.split_once("; ").unwrap();
-            let lhs = lhs.strip_prefix("Valve ").unwrap();
-            let (name, rest) = lhs.split_at(2);
-            let flow_rate = rest.strip_prefix(" has flow rate=").unwrap().parse().unwrap();
+impl ProboscideaVolcanium {
+    fn parse(data: &str, params: &crate::Params) -> Result<Self> {
+        let valves: Vec<Valve> = parse::lines(data, |l| {
+            let [name, flow_rate, rhs] = parse::template("Valve {} has flow rate={}; {}", l)?;
             let tunnels: Vec<_> = rhs
                 .strip_prefix("tunnels lead to valves ")
                 .or(rhs.strip_prefix("tunnel leads to valve "))
-                .unwrap().split(", ").collect();
-            (name, flow_rate, tunnels)
-
-        }).collect();
+                .with_context(|| format!("Invalid tunnels {:?}", rhs))?
+                .split(", ").collect();
+            Ok((name, parse::value(flow_rate)?, tunnels))
+        })?;
 
         let shortest_path_lengths_uncompressed = floyd_warshall(&valves);
 
@@ -59,10 +89,18 @@ impl crate::Advent for ProboscideaVolcanium {
                     }).collect()
             }).collect();
 
+        if interesting_valve_indices.len() > Visited::BITS as usize {
+            bail!(
+                "Tracks at most {} valves, the start and the ones with a flow rate, got {}",
+                Visited::BITS,
+                interesting_valve_indices.len()
+            );
+        }
+
         let starting_node = interesting_valve_indices
             .iter()
             .position(|&i| valves[i].0 == "AA")
-            .unwrap();
+            .context("No valve AA to start from")?;
 
         let sorted_flow_rate_indices: Vec<_> = flow_rates
             .iter()
@@ -71,12 +109,30 @@ impl crate::Advent for ProboscideaVolcanium {
             .map(|(i, _)| i)
             .collect();
                 
-        Self { 
+        Ok(Self { 
             flow_rates, 
             shortest_path_lengths, 
             flow_rate_indices: sorted_flow_rate_indices, 
-            starting_node
-        }
+            starting_node,
+            minutes_part_01: params.get("minutes_part_01"),
+            minutes_part_02: params.get("minutes_part_02"),
+            strategy_part_02: params.get("strategy_part_02")
+        })
+    }
+}
+
+impl crate::Advent for ProboscideaVolcanium {
+    fn new(data: &str, params: &crate::Params) -> Self {
+        Self::parse(data, params).unwrap()
+    }
+
+    fn parameters() -> &'static [crate::Parameter] {
+        const PARAMETERS: &[crate::Parameter] = &[
+            crate::Parameter::new::<u8>("minutes_part_01", "30", "30"),
+            crate::Parameter::new::<u8>("minutes_part_02", "26", "26"),
+            crate::Parameter::new::<Strategy>("strategy_part_02", "exact", "exact"),
+        ];
+        PARAMETERS
     }
 
     fn part_01(&self) -> String {
@@ -85,37 +141,42 @@ impl crate::Advent for ProboscideaVolcanium {
             &self.flow_rates, 
             &self.flow_rate_indices, 
             &self.shortest_path_lengths, 
-            State::new(self.starting_node as u8, 30),
-            &mut [], 
-            &mut best, 
-            |bound, best| bound > best
+            State::new(self.starting_node as u8, self.minutes_part_01),
+            &mut HashMap::new(),
+            &mut best,
+            &|bound, best| bound > best
         );
         best.to_string()
     }
 
     fn part_02(&self) -> String {
-        let mut best_per_visited = vec![0; u16::MAX as usize];
+        let mut best_per_visited = HashMap::new();
+        let filter_bound: &dyn Fn(u32, u32) -> bool = match self.strategy_part_02 {
+            // Every path has to be explored, a path that isn't the best on
+            // its own can still be the best complement to the other one
+            Strategy::Exact => &|_, _| true,
+            // This could techically produce an incorrect result,
+            // but it doesn't on my input
+            Strategy::Heuristic => &|bound, best| bound > best * 3 / 4
+        };
         branch_and_bound(
-            &self.flow_rates, 
-            &self.flow_rate_indices, 
-            &self.shortest_path_lengths, 
-            State::new(self.starting_node as u8, 26),
-            &mut best_per_visited, 
+            &self.flow_rates,
+            &self.flow_rate_indices,
+            &self.shortest_path_lengths,
+            State::new(self.starting_node as u8, self.minutes_part_02),
+            &mut best_per_visited,
             &mut 0,
-            // This could techically produce an incorrect result, 
-            // but it doesn't on my input
-            |bound, best| bound > best * 3 / 4
+            filter_bound
         );
 
         let best_per_visited_filtered_sorted: Vec<_> = best_per_visited
             .into_iter()
-            .enumerate()
             .filter(|&(_, best)| best > 0)
-            .map(|(i, best)| (i as u16, best))
             .sorted_unstable_by_key(|&(_, best)| Reverse(best))
             .collect();
 
-        let mut best = 0;
+        // The elephant may as well stay put
+        let mut best = best_per_visited_filtered_sorted.first().map_or(0, |&(_, best)| best);
 
         for (i, &(my_visited, my_best)) in best_per_visited_filtered_sorted.iter().enumerate() {
             for &(elephant_visited, elephant_best) in &best_per_visited_filtered_sorted[i + 1..] {
@@ -176,13 +237,12 @@ fn branch_and_bound(
     sorted_flow_rate_indices: &[usize],
     shortest_path_lengths: &ShortesPathLenghts,
     state: State,
-    best_for_visited: &mut [u16],
-    best: &mut u16,
-    filter_bound: impl Fn(u16, u16) -> bool + Copy
+    best_for_visited: &mut HashMap<Visited, u32>,
+    best: &mut u32,
+    filter_bound: &dyn Fn(u32, u32) -> bool
 ) {
-    if let Some(cur_best) = best_for_visited.get_mut(state.visited as usize) {
-        *cur_best = state.pressure_released.max(*cur_best);
-    }
+    let cur_best = best_for_visited.entry(state.visited).or_default();
+    *cur_best = state.pressure_released.max(*cur_best);
     *best = state.pressure_released.max(*best);
 
     let bound_branch_pairs: Vec<_> = state
@@ -202,9 +262,9 @@ fn branch_and_bound(
 
 #[derive(Default, Debug, Clone, Copy)]
 struct State {
-    visited: u16,
-    avoid: u16,
-    pressure_released: u16,
+    visited: Visited,
+    avoid: Visited,
+    pressure_released: u32,
     minutes_remaining: u8,
     position: u8,
 }
@@ -227,7 +287,7 @@ impl State {
     // Assuming the shortest path lengths are all 1, the best
     // solution is to visit the valves in order of descending 
     // flow rate.
-    fn bound(self, flow_rates: &FlowRates, sorted_flow_rate_indices: &[usize]) -> u16 {
+    fn bound(self, flow_rates: &FlowRates, sorted_flow_rate_indices: &[usize]) -> u32 {
         self.pressure_released + (0..=self.minutes_remaining)
             .rev()
             .step_by(2)
@@ -238,8 +298,8 @@ impl State {
                     .filter(|&&i| self.can_visit(i))
                     .map(|&i| flow_rates[i])
             )
-            .map(|(minutes, flow)| minutes as u16 * flow as u16)
-            .sum::<u16>()
+            .map(|(minutes, flow)| minutes as u32 * flow as u32)
+            .sum::<u32>()
     }
 
     fn branch<'a> (self, flow_rates: &'a FlowRates, shortest_path_lengths: &'a ShortesPathLenghts) -> impl IntoIterator<Item=Self> + 'a {
@@ -252,10 +312,61 @@ impl State {
                 Some(State {
                     visited: self.visited | (1 << destination),
                     avoid: self.avoid,
-                    pressure_released: self.pressure_released + minutes_remaining as u16 * flow_rates[destination] as u16,
+                    pressure_released: self.pressure_released + minutes_remaining as u32 * flow_rates[destination] as u32,
                     minutes_remaining,
                     position: destination as u8
                 })
             })
     }
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Advent;
+
+    fn volcano(data: &str, strategy: &str) -> ProboscideaVolcanium {
+        let overrides = HashMap::from([("strategy_part_02".to_string(), strategy.to_string())]);
+        let params = crate::Params::resolve(ProboscideaVolcanium::parameters(), true, &overrides).unwrap();
+        ProboscideaVolcanium::new(data, &params)
+    }
+
+    #[test]
+    fn test_strategies() {
+        let data = include_str!("example.txt");
+        assert_eq!(volcano(data, "exact").part_02(), "1707");
+        assert_eq!(volcano(data, "heuristic").part_02(), "1707");
+        assert!("fast".parse::<Strategy>().is_err());
+    }
+
+    #[test]
+    fn test_many_valves() {
+        // A corridor of 20 valves with a flow rate, more than a u16 can track
+        let names: Vec<String> = (0..=20).map(|i| if i == 0 { "AA".to_string() } else { format!("V{}", i) }).collect();
+        let data: Vec<String> = names.iter().enumerate().map(|(i, name)| {
+            let tunnels: Vec<&str> = [i.checked_sub(1), Some(i + 1)].into_iter()
+                .flatten()
+                .filter_map(|j| names.get(j).map(String::as_str))
+                .collect();
+            let flow_rate = if i == 0 { 0 } else { 1 };
+            match tunnels[..] {
+                [tunnel] => format!("Valve {} has flow rate={}; tunnel leads to valve {}", name, flow_rate, tunnel),
+                _ => format!("Valve {} has flow rate={}; tunnels lead to valves {}", name, flow_rate, tunnels.join(", "))
+            }
+        }).collect();
+        let volcano = volcano(&data.join("\n"), "exact");
+        assert_eq!(volcano.flow_rates.len(), 21);
+        // Walking down the corridor opening every valve on the way
+        assert_eq!(volcano.part_01(), (1..=14).map(|k| 30 - 2 * k).sum::<usize>().to_string());
+    }
+
+    #[test]
+    fn test_too_many_valves() {
+        let mut data: Vec<String> = (0..65).map(|i| format!("Valve V{} has flow rate=1; tunnel leads to valve AA", i)).collect();
+        data.push("Valve AA has flow rate=0; tunnel leads to valve V0".to_string());
+        let params = crate::Params::resolve(ProboscideaVolcanium::parameters(), true, &HashMap::new()).unwrap();
+        let error = ProboscideaVolcanium::parse(&data.join("\n"), &params).unwrap_err();
+        assert_eq!(error.to_string(), "Tracks at most 64 valves, the start and the ones with a flow rate, got 66");
+    }
+}
@@ -1,65 +1,92 @@
 use std::fmt::Display;
 use std::ops::Range;
-use std::fs;
-use anyhow::Context;
+use crate::utils::input::sections;
+use crate::utils::cycle::CycleDetector;
+use anyhow::{Result, anyhow, bail};
+
+const SURFACE_ROWS: usize = 64;
 
 pub struct PyroclasticFlow {
     rock_shapes: Vec<Rock>,
     jet_patterns: Vec<char>,
+    rocks_part_01: u64,
+    rocks_part_02: u64,
 }
 
 impl crate::Advent for PyroclasticFlow {
-    fn new(data: &str) -> Self {
-        let filename = "src/day_17/rock_shapes.txt";
-        let mut rock_shapes_str: &str = &fs::read_to_string(filename)
-        .with_context(|| format!("Could not read {} file.", filename)).unwrap();
-        rock_shapes_str = rock_shapes_str.trim_end();
-        let mut rock_shapes: Vec<Rock> = rock_shapes_str.split("\r\n\r\n").map(|s| {            
-            let mut width = 0;
-            let chars = s.lines().map(|l| {
-                let mut row: u8 = 0b0000_0000;
-                for (i, c) in l.chars().rev().enumerate() {
-                    if c == '#' {
-                        row |= 2u8.pow(i as u32);
-                    }                    
-                }
-                let curr_width = 8 - row.leading_zeros();
-                if curr_width > width {
-                    width = curr_width;
-                }
-                row
-            }).collect();
-            Rock::new(chars, width as usize)
-        }).collect();       
-
-        // Shifting all rocks 2 positions from left side
-        for rock_shape in rock_shapes.iter_mut() {
-            for row in rock_shape.structure.iter_mut() {
-                *row <<= 8 - rock_shape.width - 3;
-            }
-        }
+    fn new(data: &str, params: &crate::Params) -> Self {
+        let rock_shapes = parse_shapes(params.aux("rock_shapes")).unwrap();
         let line = data.lines().next().unwrap();
         let jet_patterns = line.chars().collect();
 
-        Self { rock_shapes, jet_patterns }
+        Self {
+            rock_shapes,
+            jet_patterns,
+            rocks_part_01: params.get("rocks_part_01"),
+            rocks_part_02: params.get("rocks_part_02"),
+        }
+    }
+
+    fn parameters() -> &'static [crate::Parameter] {
+        const PARAMETERS: &[crate::Parameter] = &[
+            crate::Parameter::new::<u64>("rocks_part_01", "2022", "2022"),
+            crate::Parameter::new::<u64>("rocks_part_02", "1000000000000", "1000000000000"),
+        ];
+        PARAMETERS
     }
 
     fn part_01(&self) -> String {
         let mut tower_builder = TowerBuilder::new();
-        let height = tower_builder.add_rocks(2022, &self.jet_patterns, &self.rock_shapes);    
+        let height = tower_builder.add_rocks(self.rocks_part_01, &self.jet_patterns, &self.rock_shapes);    
         height.to_string()        
     }
 
     fn part_02(&self) -> String {    
         let mut tower_builder = TowerBuilder::new();
-        let height = tower_builder.add_rocks(1000000000000, &self.jet_patterns, &self.rock_shapes);
+        let height = tower_builder.add_rocks(self.rocks_part_02, &self.jet_patterns, &self.rock_shapes);
         height.to_string()
     }
 }
 
+/// Widest rock that still spawns two columns away from the left wall.
+const MAX_ROCK_WIDTH: usize = 5;
+
+/// Parses rock shapes separated by empty lines, `#` is rock and `.` is
+/// air. Rows start at the left edge of the shape, shorter rows end with air.
+fn parse_shapes(data: &str) -> Result<Vec<Rock>> {
+    let mut rock_shapes: Vec<Rock> = sections(data).enumerate().map(|(n, s)| {
+        let lines: Vec<&str> = s.lines().collect();
+        for l in lines.iter() {
+            if let Some(c) = l.chars().find(|c| *c != '#' && *c != '.') {
+                bail!("Shape {} contains {:?}, only '#' and '.' are allowed", n + 1, c);
+            }
+        }
+        // Columns up to the rightmost rock of any row
+        let width = lines.iter().filter_map(|l| l.rfind('#')).max()
+            .ok_or_else(|| anyhow!("Shape {} has no rock", n + 1))? + 1;
+        if width > MAX_ROCK_WIDTH {
+            bail!("Shape {} is wider than {} columns", n + 1, MAX_ROCK_WIDTH);
+        }
+        let rows = lines.iter().map(|l| {
+            l.chars().enumerate()
+                .filter(|(_, c)| *c == '#')
+                .fold(0u8, |row, (x, _)| row | 1 << (width - 1 - x))
+        }).collect();
+        Ok(Rock::new(rows, width))
+    }).collect::<Result<_>>()?;
+
+    // Shifting all rocks 2 positions from left side
+    for rock_shape in rock_shapes.iter_mut() {
+        for row in rock_shape.structure.iter_mut() {
+            *row <<= 8 - rock_shape.width - 3;
+        }
+    }
+    Ok(rock_shapes)
+}
+
 type Map = Vec<u8>;
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 struct Rock {
     structure: Map, 
     height: usize,
@@ -113,7 +140,7 @@ impl Tower {
     }
 
     fn add_rock(&mut self, rock: &Rock, rock_y: usize) {
-        for (ty, row) in rock.tower_relative_iter(rock_y as usize) {
+        for (ty, row) in rock.tower_relative_iter(rock_y) {
             if ty + 1 > self.height() {
                 self.data.push(*row)
             } else {
@@ -122,33 +149,12 @@ impl Tower {
         }
     }    
 
-    fn check_for_pattern(&self) -> Option<Pattern> {
-        // Returns (pattern_start_index, pattern_height) if the pattern is found 
-        let mut pattern_range = None;
-        let size = 100;
-        let subset_it = self.data.windows(size).enumerate();
-        'search: for (offset, subset) in subset_it {
-            let mut superset_it = self.data.windows(size).enumerate().skip(offset + 1);
-            let mut prev_i: Option<usize> = None;
-            while let Some((i, superset)) = superset_it.next() {
-                if superset == subset {
-                    if let Some(prev_i_val) = prev_i {
-                        pattern_range = Some( Pattern {
-                            start_index: prev_i_val,
-                            height: i - prev_i_val
-                        });
-                        break 'search;
-                    } else {
-                        prev_i = Some(i);
-                        continue;
-                    }
-                }
-            }
-        }
-        pattern_range
+    /// The top rows of the tower, enough to tell how future rocks settle.
+    fn surface(&self) -> Vec<u8> {
+        let rows = std::cmp::min(SURFACE_ROWS, self.height());
+        self.data[self.height() - rows..].to_vec()
     }
 
-
     #[allow(dead_code)]
     fn display_with_rock(&self, rock: &Rock, rock_y: usize) {
         let mut clon = self.clone();
@@ -201,127 +207,110 @@ struct TowerBuilder {
     tower: Tower,
     rocks_count: u64,
     jet_index: usize,
-    virtual_height: u64,}
-
-#[derive(Debug)]
-struct Pattern {
-    start_index: usize,
-    height: usize
 }
 
-impl<'a> TowerBuilder {
+impl TowerBuilder {
     fn new() -> Self {
         Self {
             tower: Tower::new(),
             rocks_count: 0,
             jet_index: 0,
-            virtual_height: 0,
         }
     }
 
     fn height(&self) -> u64 {
-        self.virtual_height + self.tower.height() as u64
+        self.tower.height() as u64
     }
 
-    fn get_next_rock(&self, rock_shapes: &Vec<Rock>) -> Rock {
+    fn get_next_rock(&self, rock_shapes: &[Rock]) -> Rock {
         rock_shapes[(self.rocks_count % rock_shapes.len() as u64) as usize].clone()
     }
 
-    fn add_rocks(&mut self, rocks_limit: u64, jet_patterns: &Vec<char>, rock_shapes: &Vec<Rock>) -> u64 {
-        let mut jet_patterns_it = jet_patterns.iter().skip(self.jet_index).enumerate().cycle();    
-
-        let mut pattern_o: Option<Pattern> = None;
-        let mut count_pattern_rocks_until_height: Option<u64> = None;
-        let mut pattern_rock_count = 0;
-        let mut done_pattern_check = false;
-
-        'add_rock: while self.rocks_count < rocks_limit {       
-            let mut rock = self.get_next_rock(rock_shapes).clone();
-            // The rock is originally positioned at 2 spaces from left. No need to track that.
-            let mut rock_y: isize = self.tower.spawn_height() as isize; 
-
-            let last_jet_index = 'move_rock: loop {            
-                let (jet_index, jet) = jet_patterns_it.next().unwrap();                
-                // Check boundaries
-                let in_boundries = match jet {
-                    '>' => {!rock.structure.iter().any(|r| r.trailing_zeros() == 0)},
-                    '<' => {!rock.structure.iter().any(|r| r.leading_zeros() < 2)},
+    fn drop_rock(&mut self, jet_patterns: &[char], rock_shapes: &[Rock]) {
+        let mut rock = self.get_next_rock(rock_shapes);
+        // The rock is originally positioned at 2 spaces from left. No need to track that.
+        let mut rock_y: isize = self.tower.spawn_height() as isize;
+
+        loop {
+            let jet = jet_patterns[self.jet_index];
+            self.jet_index = (self.jet_index + 1) % jet_patterns.len();
+            // Check boundaries
+            let in_boundries = match jet {
+                '>' => {!rock.structure.iter().any(|r| r.trailing_zeros() == 0)},
+                '<' => {!rock.structure.iter().any(|r| r.leading_zeros() < 2)},
+                _ => unreachable!()
+            };
+            if in_boundries {
+                // Create a clone and move it
+                let mut moved_rock = rock.clone();
+                match jet {
+                    '>' => moved_rock.structure.iter_mut().for_each(|r| *r >>= 1),
+                    '<' => moved_rock.structure.iter_mut().for_each(|r| *r <<= 1),
                     _ => unreachable!()
                 };
-                if in_boundries {
-                    // Create a clone and move it
-                    let mut moved_rock = rock.clone();
-                    match jet {
-                        '>' => moved_rock.structure.iter_mut().for_each(|r| *r >>= 1),
-                        '<' => moved_rock.structure.iter_mut().for_each(|r| *r <<= 1),
-                        _ => unreachable!()
-                    };         
-
-                    // Check if newly moved value has a valid position in tower
-                    if self.tower.is_rock_position_valid(&moved_rock, rock_y) {
-                        // Replace with moved rock
-                        rock = moved_rock;
-                    }
-                }
-                
-                if rock_y == 0 {
-                    // Collides
-                    break 'move_rock jet_index;                                        
+
+                // Check if newly moved value has a valid position in tower
+                if self.tower.is_rock_position_valid(&moved_rock, rock_y) {
+                    // Replace with moved rock
+                    rock = moved_rock;
                 }
+            }
 
-                let new_rock_y = rock_y - 1;
-                if self.tower.is_rock_position_valid(&rock, new_rock_y) {
-                    rock_y = new_rock_y;
-                } else {
-                    // Collides             
-                    break 'move_rock jet_index;  
-                }            
-            };
+            if rock_y == 0 || !self.tower.is_rock_position_valid(&rock, rock_y - 1) {
+                // Collides
+                break;
+            }
+            rock_y -= 1;
+        }
 
-            self.jet_index = last_jet_index;
-            // Update tower
-            self.tower.add_rock(&rock, rock_y as usize);            
-            self.rocks_count += 1;                       
+        self.tower.add_rock(&rock, rock_y as usize);
+        self.rocks_count += 1;
+    }
 
-            if !done_pattern_check {
-                if pattern_o.is_none() && self.rocks_count % 1000 == 0 {
-                    pattern_o = self.tower.check_for_pattern();                                
-                }
-    
-                if let Some(pattern) = pattern_o.as_ref() {
-                    match count_pattern_rocks_until_height {
-                        None => {
-                            // Pass another pattern cycle, this time counting the number of rocks in it.
-                            // .. Start to count rocks
-                            let remainder = (self.tower.height() - pattern.start_index) % pattern.height;
-                            if remainder < 4 {
-                                count_pattern_rocks_until_height = Some(self.tower.height() as u64 + pattern.height as u64);
-                            }
-                        },
-                        Some(height_limit) => {
-                            let last_pattern_range = (height_limit - pattern.height as u64) as usize .. height_limit as usize;
-                            let first_pattern_range = pattern.start_index .. pattern.start_index + pattern.height;
-                            
-                            if last_pattern_range.contains(&(rock_y as usize)) {
-                                pattern_rock_count += 1;
-                            }
-                            if self.tower.data.len() < last_pattern_range.end {
-                                continue 'add_rock;
-                            }
-    
-                            // Check if pattern is complete / the same
-                            if self.tower.data[last_pattern_range] == self.tower.data[first_pattern_range] {
-                                let mul_times = (rocks_limit - self.rocks_count) / pattern_rock_count;
-                                self.virtual_height += pattern.height as u64 * mul_times;
-                                self.rocks_count += pattern_rock_count * mul_times;
-                                done_pattern_check = true;
-                                continue 'add_rock;
-                            }
-                        }
-                    }             
-                }
-            }            
+    fn add_rocks(&mut self, rocks_limit: u64, jet_patterns: &[char], rock_shapes: &[Rock]) -> u64 {
+        // Height after each dropped rock, the tower repeats once the next rock,
+        // the next jet and the surface are the same as before
+        let mut heights = vec![self.height()];
+        let mut detector = CycleDetector::new();
+        while self.rocks_count < rocks_limit {
+            let rock_index = (self.rocks_count % rock_shapes.len() as u64) as usize;
+            let key = (rock_index, self.jet_index, self.tower.surface());
+            if let Some(cycle) = detector.push(key) {
+                return cycle.extrapolate(&heights, rocks_limit);
+            }
+            self.drop_rock(jet_patterns, rock_shapes);
+            heights.push(self.height());
         }
         self.height()
     }
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_shapes() {
+        let shapes = parse_shapes(include_str!("rock_shapes.txt")).unwrap();
+        assert_eq!(shapes.iter().map(|r| (r.width, r.height)).collect::<Vec<_>>(), vec![(4, 1), (3, 3), (3, 3), (1, 4), (2, 2)]);
+        assert_eq!(shapes[0].structure, vec![0b0001_1110]);
+        assert_eq!(parse_shapes("#\n\n######").unwrap_err().to_string(), "Shape 2 is wider than 5 columns");
+        assert_eq!(parse_shapes("#\n\n..\n..").unwrap_err().to_string(), "Shape 2 has no rock");
+        assert_eq!(parse_shapes("#####").unwrap()[0].structure, vec![0b0001_1111]);
+        assert!(parse_shapes("#\n\n#o#").unwrap_err().to_string().starts_with("Shape 2 contains 'o'"));
+    }
+
+    #[test]
+    fn test_custom_shape() {
+        // An L keeps its foot on the left, every row starts at the left edge
+        let shapes = parse_shapes("#\n###\n\n.#\n#").unwrap();
+        assert_eq!((shapes[0].width, shapes[0].height), (3, 2));
+        assert_eq!(shapes[0].structure, vec![0b0001_0000, 0b0001_1100]);
+        assert_eq!((shapes[1].width, shapes[1].height), (2, 2));
+        assert_eq!(shapes[1].structure, vec![0b0000_1000, 0b0001_0000]);
+        // Dropping it on the floor leaves it 3 columns wide at the bottom
+        let mut tower_builder = TowerBuilder::new();
+        assert_eq!(tower_builder.add_rocks(1, &['<'], &shapes[..1]), 2);
+    }
+}
@@ -1,143 +1,285 @@
-// 3284 -- too high
+use std::collections::{VecDeque, HashSet};
+use anyhow::Result;
+use crate::utils::parse;
 
-use std::{collections::{VecDeque, HashSet}, ops::RangeBounds};
+type Coord = [i32; 3];
 
-type Coord = [i8; 3];
+const NEIGHBOUR_DELTAS: [Coord; 6] = [
+    [1, 0, 0], [-1, 0, 0],
+    [0, 1, 0], [0, -1, 0],
+    [0, 0, 1], [0, 0, -1]
+];
 
-
-fn get_neighbour_coords(c: &Coord) -> Vec<Coord> {
-    let mut neighbours: Vec<Coord> = vec![];
-
-    neighbours.push([c[0] + 1, c[1], c[2]]);
-    neighbours.push([c[0] - 1, c[1], c[2]]);
-    neighbours.push([c[0], c[1] + 1, c[2]]);
-    neighbours.push([c[0], c[1] - 1, c[2]]);
-    neighbours.push([c[0], c[1], c[2] + 1]);
-    neighbours.push([c[0], c[1], c[2] - 1]);
-
-    neighbours
+fn neighbours(c: &Coord) -> impl Iterator<Item = Coord> + '_ {
+    NEIGHBOUR_DELTAS.iter().map(|d| [c[0] + d[0], c[1] + d[1], c[2] + d[2]])
 }
 
 pub struct BoilingBoulders {
-    droplets: HashSet<Coord>,
-    max_coord: Coord
+    droplet: Droplet,
+    report: bool
 }
 
 impl crate::Advent for BoilingBoulders {
-    fn new(data: &str) -> Self
-        where 
-            Self: Sized {                
-        
-        let droplets: HashSet<Coord> = data.lines().map(|l| {
-            let v: Vec<i8> = l.split(',').map(|n| n.parse().unwrap()).collect();
-            v.try_into().unwrap()
-        }).collect();
-
-        let max_x = droplets.iter().map(|d| d[0]).max().unwrap();
-        let max_y = droplets.iter().map(|d| d[1]).max().unwrap();
-        let max_z = droplets.iter().map(|d| d[2]).max().unwrap();
-        let max_coord: Coord = [max_x, max_y, max_z];
-        
-        Self { droplets, max_coord }
+    fn new(data: &str, params: &crate::Params) -> Self
+        where
+            Self: Sized {
+        let cubes = parse_cubes(data).unwrap();
+        Self { droplet: Droplet::new(cubes), report: params.get("report") }
+    }
+
+    fn parameters() -> &'static [crate::Parameter] {
+        const PARAMETERS: &[crate::Parameter] = &[
+            crate::Parameter::new::<bool>("report", "false", "false"),
+        ];
+        PARAMETERS
     }
 
     fn part_01(&self) -> String {
-        let surface_area = self.surface_area(&self.droplets);
-        surface_area.to_string()
+        self.droplet.surface_area().to_string()
     }
 
     fn part_02(&self) -> String {
-        let initial_surface_area = self.surface_area(&self.droplets);
-        let air_pockets = self.get_air_pockets();
-
-        let mut air_pockets_surface_area = 0;
-        for air_pocket in air_pockets {
-            let air_pocket_surface_area = self.surface_area(&air_pocket);
-            air_pockets_surface_area += air_pocket_surface_area;
+        if self.report {
+            let droplet = &self.droplet;
+            println!("Exterior area: {}", droplet.exterior_area());
+            println!("Interior area: {}", droplet.interior_area());
+            println!("Pocket volumes: {:?}", droplet.pocket_volumes());
+            println!("Enclosed volume: {}", droplet.enclosed_volume());
         }
-
-        let surface_area = initial_surface_area - air_pockets_surface_area;
-        surface_area.to_string()
+        self.droplet.exterior_area().to_string()
     }
 }
 
-impl BoilingBoulders {
-    fn surface_area(&self, spaces: &HashSet<Coord>) -> usize {
-        let mut surface_area = 0;
-        for coord in spaces.iter() {
-            for neighbour_coord in get_neighbour_coords(coord) {
-                if !spaces.contains(&neighbour_coord) {
-                    surface_area += 1;
+fn parse_cubes(data: &str) -> Result<HashSet<Coord>> {
+    let cubes = parse::lines(data, |l| {
+        let [x, y, z] = parse::template("{},{},{}", l)?;
+        Ok([parse::value(x)?, parse::value(y)?, parse::value(z)?])
+    })?;
+    Ok(cubes.into_iter().collect())
+}
+
+/// Lava cubes that touch each other, by a face, an edge or a corner.
+/// Air enclosed by the droplet is always enclosed by a single piece.
+struct Piece {
+    cubes: HashSet<Coord>,
+    min: Coord,
+    max: Coord,
+    // Air next to the piece that is reachable from outside of it
+    outside: HashSet<Coord>
+}
+
+impl Piece {
+    fn new(cubes: HashSet<Coord>) -> Self {
+        let mut min = [i32::MAX; 3];
+        let mut max = [i32::MIN; 3];
+        for cube in cubes.iter() {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(cube[axis]);
+                max[axis] = max[axis].max(cube[axis]);
+            }
+        }
+        let mut piece = Piece { cubes, min, max, outside: HashSet::new() };
+        // Nothing of the piece is further to the left
+        let start = piece.cubes.iter().min().map(|c| [c[0] - 1, c[1], c[2]]).unwrap();
+        piece.outside = piece.flood_fill_shell(start);
+        piece
+    }
+
+    /// Air connected to `start` without leaving the cells that touch the
+    /// piece, so the work grows with the surface and not with the box.
+    fn flood_fill_shell(&self, start: Coord) -> HashSet<Coord> {
+        let in_shell = |c: &Coord| {
+            !self.cubes.contains(c) && around(c).any(|a| self.cubes.contains(&a))
+        };
+        let mut queue: VecDeque<Coord> = VecDeque::from([start]);
+        let mut visited: HashSet<Coord> = HashSet::from([start]);
+        while let Some(c) = queue.pop_front() {
+            for n in neighbours(&c) {
+                if in_shell(&n) && visited.insert(n) {
+                    queue.push_back(n);
                 }
             }
         }
-        surface_area
+        visited
     }
 
-    fn empty_neighbours_it<'a> (&'a self, c: &'a Coord) -> impl Iterator<Item = Coord> + 'a {
-        get_neighbour_coords(c).into_iter().filter_map(|nc| {
-            if self.droplets.contains(&nc) {
-                return None;
+    /// Whether air at `c` isn't enclosed by the piece. Walks left until the
+    /// piece, the air right before it is in the same place as `c`.
+    fn is_outside(&self, c: &Coord) -> bool {
+        if (0..3).any(|axis| !(self.min[axis]..=self.max[axis]).contains(&c[axis])) {
+            return true;
+        }
+        let mut current = *c;
+        while current[0] >= self.min[0] {
+            let next = [current[0] - 1, current[1], current[2]];
+            if self.cubes.contains(&next) {
+                return self.outside.contains(&current);
             }
-            Some(nc)
-        })
-    } 
+            current = next;
+        }
+        true
+    }
+}
 
-    fn search_out_of_bounds<R: RangeBounds<i8>>(&self, start: &Coord, bounds: &[R; 3]) -> (bool, HashSet<Coord>) {
-        let mut queue: VecDeque<Coord> = VecDeque::new();
-        let mut visited: HashSet<Coord> = HashSet::new();
+/// Cells around `c`, diagonals included.
+fn around(c: &Coord) -> impl Iterator<Item = Coord> + '_ {
+    (-1..=1).flat_map(move |dx| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| {
+        [c[0] + dx, c[1] + dy, c[2] + dz]
+    }))).filter(move |a| a != c)
+}
 
-        queue.push_back(start.clone());
-        visited.insert(start.clone());
+/// Scanned lava droplet and the air around it. Air that can't be reached
+/// from far away is trapped in pockets.
+struct Droplet {
+    cubes: HashSet<Coord>,
+    pieces: Vec<Piece>
+}
 
-        while let Some(c) = queue.pop_front() {            
-            for empty_neighbour in self.empty_neighbours_it(&c) {
-                if visited.contains(&empty_neighbour) {
-                    continue;
+impl Droplet {
+    fn new(cubes: HashSet<Coord>) -> Self {
+        let mut pieces = vec![];
+        let mut seen: HashSet<Coord> = HashSet::new();
+        for cube in cubes.iter() {
+            if !seen.insert(*cube) {
+                continue;
+            }
+            let mut piece = HashSet::from([*cube]);
+            let mut stack = vec![*cube];
+            while let Some(c) = stack.pop() {
+                for a in around(&c) {
+                    if cubes.contains(&a) && seen.insert(a) {
+                        piece.insert(a);
+                        stack.push(a);
+                    }
                 }
+            }
+            pieces.push(Piece::new(piece));
+        }
+        Droplet { cubes, pieces }
+    }
+
+    fn is_exterior(&self, c: &Coord) -> bool {
+        !self.cubes.contains(c) && self.pieces.iter().all(|p| p.is_outside(c))
+    }
+
+    /// Faces of the cubes that don't touch another cube.
+    fn surface_area(&self) -> usize {
+        self.cubes.iter()
+            .flat_map(neighbours)
+            .filter(|n| !self.cubes.contains(n))
+            .count()
+    }
 
-                visited.insert(empty_neighbour.clone());
-                for (axis, val) in empty_neighbour.iter().enumerate() {
-                    if !bounds[axis].contains(val) {
-                        // Is out of bounds / can't be an air bubble
-                        return (true, visited);
+    /// Faces reachable by the outside air.
+    fn exterior_area(&self) -> usize {
+        self.cubes.iter()
+            .flat_map(neighbours)
+            .filter(|n| self.is_exterior(n))
+            .count()
+    }
+
+    /// Faces facing the air pockets.
+    fn interior_area(&self) -> usize {
+        self.surface_area() - self.exterior_area()
+    }
+
+    /// Every connected pocket of trapped air, starting from the air next to
+    /// the lava so only the pockets themselves are filled.
+    fn pockets(&self) -> Vec<HashSet<Coord>> {
+        let mut starts: Vec<Coord> = self.cubes.iter()
+            .flat_map(neighbours)
+            .filter(|n| !self.cubes.contains(n) && !self.is_exterior(n))
+            .collect();
+        starts.sort_unstable();
+        let mut pockets: Vec<HashSet<Coord>> = vec![];
+        let mut seen: HashSet<Coord> = HashSet::new();
+        for start in starts {
+            if seen.contains(&start) {
+                continue;
+            }
+            // The pocket is enclosed, so this stops on its own
+            let mut queue: VecDeque<Coord> = VecDeque::from([start]);
+            let mut pocket: HashSet<Coord> = HashSet::from([start]);
+            while let Some(c) = queue.pop_front() {
+                for n in neighbours(&c) {
+                    if !self.cubes.contains(&n) && pocket.insert(n) {
+                        queue.push_back(n);
                     }
                 }
-                queue.push_back(empty_neighbour.clone());                
             }
+            seen.extend(pocket.iter());
+            pockets.push(pocket);
         }
+        pockets
+    }
 
-        // We have no more empty cells to check
-        // This air cell and all visited are an air pocket    
-        (false, visited)
+    fn pocket_volumes(&self) -> Vec<usize> {
+        self.pockets().iter().map(HashSet::len).collect()
     }
 
-    fn get_air_pockets(&self) -> Vec<HashSet<Coord>>{
-        let mut air_pockets: Vec<HashSet<Coord>> = vec![];        
-        let mut visited: Vec<Coord> = vec![];      
+    fn enclosed_volume(&self) -> usize {
+        self.pocket_volumes().iter().sum()
+    }
+}
 
-        let bounds = [
-            1..self.max_coord[0],
-            1..self.max_coord[1],
-            1..self.max_coord[2]
-        ];
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn droplet(data: &str) -> Droplet {
+        Droplet::new(parse_cubes(data).unwrap())
+    }
+
+    #[test]
+    fn test_example() {
+        let droplet = droplet(include_str!("example.txt"));
+        assert_eq!(droplet.surface_area(), 64);
+        assert_eq!(droplet.exterior_area(), 58);
+        assert_eq!(droplet.interior_area(), 6);
+        assert_eq!(droplet.pocket_volumes(), vec![1]);
+    }
 
-        for x in bounds[0].clone() {
-            for y in bounds[1].clone() {
-                for z in bounds[2].clone() {
-                    let c = [x, y, z];
-
-                    // Is air and has not yet been visited
-                    if !self.droplets.contains(&c) && !visited.contains(&c) {
-                        let (out_of_bounds, new_visited) = self.search_out_of_bounds(&c, &bounds);
-                        if !out_of_bounds {
-                            air_pockets.push(new_visited.clone());
-                        }
-                        visited.extend(new_visited.into_iter());                        
+    #[test]
+    fn test_hollow_cube() {
+        // 5x5x5 shell around a 3x3x3 hole, plus a cube inside the hole
+        let mut cubes: HashSet<Coord> = HashSet::new();
+        for x in 0..5 {
+            for y in 0..5 {
+                for z in 0..5 {
+                    if [x, y, z].iter().any(|v| *v == 0 || *v == 4) {
+                        cubes.insert([x, y, z]);
                     }
                 }
             }
         }
-        air_pockets   
+        cubes.insert([2, 2, 2]);
+        let droplet = Droplet::new(cubes);
+        assert_eq!(droplet.exterior_area(), 6 * 25);
+        assert_eq!(droplet.interior_area(), 6 * 9 + 6);
+        assert_eq!(droplet.enclosed_volume(), 27 - 1);
+        assert_eq!(droplet.pockets().len(), 1);
+    }
+
+    #[test]
+    fn test_wide_coordinates() {
+        // Way past what an i8 holds
+        let wide = droplet("1000,-500,200\n1001,-500,200\n1003,-500,200");
+        assert_eq!(wide.surface_area(), 10 + 6);
+        assert_eq!(wide.exterior_area(), 10 + 6);
+        assert!(parse_cubes("1,2").is_err());
+        // Only the air next to the lava is filled, not the box between
+        let far = droplet("0,0,0\n1000000,1000000,1000000\n-1000000,5,-1000000");
+        assert_eq!(far.exterior_area(), 3 * 6);
+        assert_eq!(far.enclosed_volume(), 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_nested_pieces() {
+        // Cubes around a cell touch each other by their edges only, they
+        // still form a single piece holding the air
+        let droplet = droplet("1,0,0\n0,1,0\n2,1,0\n1,2,0\n1,1,1\n1,1,-1");
+        assert_eq!(droplet.pieces.len(), 1);
+        assert_eq!(droplet.pocket_volumes(), vec![1]);
+        assert_eq!(droplet.exterior_area(), 6 * 6 - 6);
+    }
+}
@@ -2,59 +2,85 @@ use std::str::FromStr;
 use anyhow::{Result, Error, anyhow};
 use std::collections::HashMap;
 use std::cmp::Ord;
+use std::thread;
+use crate::utils::parse;
+
+type Blueprint = HashMap<Mineral, HashMap<Mineral, usize>>;
+
 pub struct NotEnoughMinerals {
-    blueprints: Vec<HashMap<Mineral, HashMap<Mineral, usize>>>
+    blueprints: Vec<Blueprint>,
+    minutes_part_01: usize,
+    minutes_part_02: usize,
+    build_order: bool
 }
 
 impl crate::Advent for NotEnoughMinerals {
-    fn new(data: &str) -> Self
+    fn new(data: &str, params: &crate::Params) -> Self
         where 
             Self: Sized {
-        let blueprints = data.lines().map(|l| {
-            let (_, robot_strings) = l.split_once(": ").unwrap();
-            let robot_strings = robot_strings.strip_suffix(".").unwrap();
-            robot_strings.split(". ").map(|rs| {            
-                let words: Vec<_> = rs.split(" ").collect();
-                let mines = Mineral::from_str(words[1]).unwrap();
-                let mut robots: HashMap<Mineral, usize> = HashMap::new();
-                
-                let mut costs_index = 4;
-                while costs_index < words.len() {
-                    robots.insert(
-                        Mineral::from_str(words[costs_index + 1]).unwrap(),
-                        words[costs_index].parse().unwrap()
-                    );
-                    costs_index += 3;
-                }
-                (mines, robots)
+        let blueprints = parse::lines(data, |l| {
+            let [_id, robot_strings] = parse::template("Blueprint {}: {}.", l)?;
+            robot_strings.split(". ").map(|rs| {
+                let [mines, costs] = parse::template("Each {} robot costs {}", rs)?;
+                let robots = costs.split(" and ").map(|cost| {
+                    let [amount, mineral] = parse::template("{} {}", cost)?;
+                    Ok((Mineral::from_str(mineral)?, parse::value(amount)?))
+                }).collect::<Result<HashMap<Mineral, usize>>>()?;
+                Ok((Mineral::from_str(mines)?, robots))
             }).collect()
-        }).collect();
-        Self { blueprints }
+        }).unwrap();
+        Self {
+            blueprints,
+            minutes_part_01: params.get("minutes_part_01"),
+            minutes_part_02: params.get("minutes_part_02"),
+            build_order: params.get("build_order")
+        }
+    }
+
+    fn parameters() -> &'static [crate::Parameter] {
+        const PARAMETERS: &[crate::Parameter] = &[
+            crate::Parameter::new::<usize>("minutes_part_01", "24", "24"),
+            crate::Parameter::new::<usize>("minutes_part_02", "32", "32"),
+            crate::Parameter::new::<bool>("build_order", "false", "false"),
+        ];
+        PARAMETERS
     }
     
     fn part_01(&self) -> String {
-        let mut quality_levels_sum = 0;
-        let time_limit = 24;
-        for (i, blueprint) in self.blueprints.iter().enumerate() {
-            let factory = Factory::new(blueprint.clone());              
-            let max_geodes = find_best_strategy(&factory, time_limit, 0);
-            let quality_level = (i + 1) * max_geodes;
-            quality_levels_sum += quality_level;
-        }
-        
-        quality_levels_sum.to_string()
+        self.best_strategies(&self.blueprints, self.minutes_part_01)
+            .iter()
+            .enumerate()
+            .map(|(i, strategy)| (i + 1) * strategy.geodes)
+            .sum::<usize>()
+            .to_string()
     }
 
     fn part_02(&self) -> String {
-        let time_limit = 32;
-        let mut result = 1;
         let limit = std::cmp::min(self.blueprints.len(), 3);
-        for blueprint in self.blueprints[0..limit].iter() {
-            let factory = Factory::new(blueprint.clone());              
-            let max_geodes = find_best_strategy(&factory, time_limit, 2);
-            result *= max_geodes;            
+        self.best_strategies(&self.blueprints[0..limit], self.minutes_part_02)
+            .iter()
+            .map(|strategy| strategy.geodes)
+            .product::<usize>()
+            .to_string()
+    }
+}
+
+impl NotEnoughMinerals {
+    /// Solves every blueprint on its own thread.
+    fn best_strategies(&self, blueprints: &[Blueprint], time_limit: usize) -> Vec<Strategy> {
+        let strategies: Vec<Strategy> = thread::scope(|scope| {
+            let handles: Vec<_> = blueprints.iter()
+                .map(|blueprint| scope.spawn(move || find_best_strategy(blueprint, time_limit)))
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+        if self.build_order {
+            for (i, (blueprint, strategy)) in blueprints.iter().zip(strategies.iter()).enumerate() {
+                println!("Blueprint {}: {} geodes", i + 1, strategy.geodes);
+                print!("{}", strategy.replay(blueprint, time_limit));
+            }
         }
-        result.to_string()
+        strategies
     }
 }
 
@@ -67,6 +93,8 @@ enum Mineral {
     Geode
 }
 
+const MINERALS: [Mineral; 4] = [Mineral::Ore, Mineral::Clay, Mineral::Obsidian, Mineral::Geode];
+
 impl FromStr for Mineral {
     type Err = Error;
 
@@ -125,23 +153,6 @@ impl Factory {
         self.mineral_states.get_mut(mineral).unwrap()
     }
 
-    fn time_till_buy(&self, mineral: &Mineral) -> usize {
-        // In how many minutes we can buy a robot of mineral type taking into account 
-        // the current resources we have.
-
-        let state = self.state(&mineral);              
-        let max_minutes_till_buy = state.prices.iter().map(|(m, p)| {
-            let prev_state = self.state(&m);
-            if prev_state.minerals >= *p {
-                0
-            } else {
-                let remainder = *p - prev_state.minerals;
-                (remainder + prev_state.robots - 1) / prev_state.robots
-            }            
-        }).max().unwrap();
-        max_minutes_till_buy + 1
-    }
-
     fn pass_minute(&mut self, new_robot: Option<&Mineral>) {
         {
             if let Some(robot) = new_robot {
@@ -177,82 +188,167 @@ impl Factory {
             self.state_mut(&m).minerals -= price;
         }
     }
-
-    fn could_buy(&self) -> Vec<Mineral> {
-        self.mineral_states.iter().filter_map(|(mineral, state)| {
-            if state.prices.iter().all(|(m, _)| {
-                self.mineral_states[m].robots > 0
-            }) {
-                Some(*mineral)
-            } else {
-                None
-            }
-        }).collect()        
-    }
 }
 
 impl std::fmt::Display for Factory {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         writeln!(f, "-----------------------")?;
         writeln!(f, "Passed minute: {}, State:", self.time_passed)?;
-        for (m, s) in &self.mineral_states {
+        for m in MINERALS.iter() {
+            let s = self.state(m);
             writeln!(f, "{: <8} -> R: {: >2}, M: {: >2}", format!("{:?}", m), s.robots, s.minerals)?;
         }
-        write!(f, "\n")
+        writeln!(f)
     }
 }
 
-fn find_best_strategy(initial_factory: &Factory, time_limit: usize, max_diviation: usize) -> usize {
-    // Mineral to buy, time to buy, Factory
-    let mut factories: Vec<(Mineral, usize, Factory)> = Vec::new();
-    let mut max_geodes: usize = 0;
-    let mut could_buy = initial_factory.could_buy();
-    could_buy.sort_unstable();
-    for mineral in could_buy {
-        let time_till_buy = initial_factory.time_till_buy(&mineral);
-        factories.push((mineral, time_till_buy, initial_factory.clone()));
+/// Robot costs, `costs[robot][mineral]` is the amount of the mineral paid for
+/// the robot.
+struct Costs {
+    costs: [[usize; 4]; 4],
+    // Spending is capped by the most expensive robot, more robots of a
+    // mineral than that can never be used
+    max_useful: [usize; 4]
+}
+
+impl Costs {
+    fn new(blueprint: &Blueprint) -> Self {
+        let mut costs = [[0; 4]; 4];
+        for (robot, prices) in blueprint {
+            for (mineral, price) in prices {
+                costs[*robot as usize][*mineral as usize] = *price;
+            }
+        }
+        let mut max_useful = [0; 4];
+        for (m, max) in max_useful.iter_mut().enumerate() {
+            *max = costs.iter().map(|c| c[m]).max().unwrap();
+        }
+        max_useful[Mineral::Geode as usize] = usize::MAX;
+        Costs { costs, max_useful }
     }
+}
 
-    let mut max_geodes_at_time: HashMap<usize, usize> = HashMap::new();
+/// Most geodes a blueprint can open and the robots built to get them, each
+/// robot with the minute its construction starts.
+#[derive(Debug, Clone, Default)]
+struct Strategy {
+    geodes: usize,
+    build_order: Vec<(usize, Mineral)>
+}
 
-    while let Some((mineral, time_till_buy, mut factory)) = factories.pop() {        
-        let pass_time = std::cmp::min(time_till_buy, time_limit - (factory.time_passed));
-        let mut bought_robot = false;
-        for minute in 1..=pass_time {
-            if minute == time_till_buy {
-                bought_robot = true;
-                factory.pass_minute(Some(&mineral));
-            } else {
-                factory.pass_minute(None)
-            }                
+impl Strategy {
+    /// Runs the build order in a [`Factory`], showing its state after
+    /// every minute.
+    fn replay(&self, blueprint: &Blueprint, time_limit: usize) -> String {
+        let mut factory = Factory::new(blueprint.clone());
+        let mut result = String::new();
+        let mut builds = self.build_order.iter().peekable();
+        for minute in 1..=time_limit {
+            match builds.next_if(|(m, _)| *m == minute) {
+                Some((_, robot)) => {
+                    result.push_str(&format!("Minute {}: building a {:?} robot\n", minute, robot));
+                    factory.pass_minute(Some(robot));
+                },
+                None => factory.pass_minute(None)
+            }
+            result.push_str(&factory.to_string());
         }
+        result
+    }
+}
 
-        let current_max_geodes = factory.state(&Mineral::Geode).minerals;
-        if factory.time_passed >= time_limit {   
-            if current_max_geodes > max_geodes {                
-                max_geodes = current_max_geodes;                
-            }
+#[derive(Clone, Copy)]
+struct SearchState {
+    minute: usize,
+    robots: [usize; 4],
+    minerals: [usize; 4]
+}
+
+/// Depth first search over which robot to build next, waiting as long as it
+/// takes to afford it.
+fn find_best_strategy(blueprint: &Blueprint, time_limit: usize) -> Strategy {
+    let costs = Costs::new(blueprint);
+    let mut best = Strategy::default();
+    let mut build_order = vec![];
+    let start = SearchState { minute: 0, robots: [1, 0, 0, 0], minerals: [0; 4] };
+    search(&costs, time_limit, start, &mut build_order, &mut best);
+    best
+}
+
+fn search(costs: &Costs, time_limit: usize, state: SearchState, build_order: &mut Vec<(usize, Mineral)>, best: &mut Strategy) {
+    let geode = Mineral::Geode as usize;
+    let remaining = time_limit - state.minute;
+    // Geodes opened if no more robots get built
+    let geodes = state.minerals[geode] + state.robots[geode] * remaining;
+    if geodes > best.geodes || best.build_order.is_empty() && geodes == best.geodes {
+        *best = Strategy { geodes, build_order: build_order.clone() };
+    }
+    // Building a geode robot every remaining minute is the best case
+    let bound = geodes + remaining * remaining.saturating_sub(1) / 2;
+    if bound <= best.geodes {
+        return;
+    }
+
+    for robot in MINERALS.iter().rev() {
+        let r = *robot as usize;
+        if state.robots[r] >= costs.max_useful[r] {
             continue;
         }
-        let max_geodes_at_the_time = max_geodes_at_time.entry(factory.time_passed).or_default();
-        if current_max_geodes + max_diviation < *max_geodes_at_the_time {
+        let wait = (0..4).map(|m| {
+            let cost = costs.costs[r][m];
+            if state.minerals[m] >= cost {
+                Some(0)
+            } else if state.robots[m] == 0 {
+                None
+            } else {
+                Some((cost - state.minerals[m]).div_ceil(state.robots[m]))
+            }
+        }).collect::<Option<Vec<usize>>>().and_then(|waits| waits.into_iter().max());
+        let Some(wait) = wait else { continue };
+        // The robot has to be ready before the end to mine anything
+        let minute = state.minute + wait + 1;
+        if minute >= time_limit {
             continue;
-        } else if current_max_geodes > *max_geodes_at_the_time {
-            *max_geodes_at_the_time = current_max_geodes;
         }
-
-        if bought_robot {
-            let mut could_buy = factory.could_buy();
-            could_buy.sort_unstable();
-            for m in could_buy.into_iter() {                        
-                let ttb = factory.time_till_buy(&m);
-                if ttb == 0 {
-                    println!("TTB = 0! {}\nM: {:?}", factory, m);
-                    continue;
-                }
-                factories.push((m, ttb, factory.clone()))
-            }
+        let mut next = SearchState { minute, ..state };
+        for m in 0..4 {
+            next.minerals[m] = state.minerals[m] + state.robots[m] * (wait + 1) - costs.costs[r][m];
         }
+        next.robots[r] += 1;
+        build_order.push((state.minute + wait + 1, *robot));
+        search(costs, time_limit, next, build_order, best);
+        build_order.pop();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Advent;
+
+    fn example() -> NotEnoughMinerals {
+        let params = crate::Params::resolve(NotEnoughMinerals::parameters(), true, &HashMap::new()).unwrap();
+        NotEnoughMinerals::new(include_str!("example.txt"), &params)
+    }
+
+    #[test]
+    fn test_example_blueprints() {
+        let minerals = example();
+        let geodes: Vec<usize> = minerals.blueprints.iter()
+            .map(|b| find_best_strategy(b, 24).geodes)
+            .collect();
+        assert_eq!(geodes, vec![9, 12]);
     }
-    max_geodes
-}
\ No newline at end of file
+
+    #[test]
+    fn test_replay_build_order() {
+        let minerals = example();
+        let blueprint = &minerals.blueprints[0];
+        let strategy = find_best_strategy(blueprint, 24);
+        let replay = strategy.replay(blueprint, 24);
+        assert!(replay.starts_with("-----------------------\nPassed minute: 1, State:\nOre      -> R:  1, M:  1\n"));
+        assert!(replay.ends_with("M:  9\n\n"));
+        assert_eq!(replay.matches("building").count(), strategy.build_order.len());
+    }
+}
@@ -0,0 +1,102 @@
+// My solution that runs in few seconds
+
+pub struct GrovePositioningSystem {
+    file: Vec<isize>
+}
+
+impl crate::Advent for GrovePositioningSystem {
+    fn new(data: &str, _params: &crate::Params) -> Self
+        where
+            Self: Sized {
+        let file = data.lines().map(|l| {
+            l.parse().unwrap()
+        }).collect();
+        Self { file }
+    }
+
+    fn part_01(&self) -> String {
+        let indexes: Vec<usize> = (0..self.file.len()).collect();
+        let indexes = self.mix_numbers(&self.file, indexes);
+        let new_values = self.generate_new_from_indexes(&self.file, &indexes);
+        let result = self.find_groove_coordinates(new_values);
+        result.to_string()
+    }
+
+    fn part_02(&self) -> String {
+        let key = 811589153;
+        let file: Vec<isize> = self.file.iter().map(|n| *n * key).collect();
+        let mut indexes: Vec<usize> = (0..file.len()).collect();
+        for _ in 0..10 {
+            indexes = self.mix_numbers(&file, indexes);
+        }
+        let new_values = self.generate_new_from_indexes(&file, &indexes);
+        let result = self.find_groove_coordinates(new_values);
+        result.to_string()
+    }
+}
+
+impl GrovePositioningSystem {
+    fn generate_new_from_indexes(&self, original_file: &[isize], new_indexes: &[usize]) -> Vec<isize> {
+        let mut new_indexes: Vec<(usize, &usize)> = new_indexes.iter().enumerate().collect();
+        new_indexes.sort_by(|a, b| a.1.cmp(b.1));
+        new_indexes.into_iter().map(|(original, _new)| original_file[original]).collect()
+    }
+
+    fn find_groove_coordinates(&self, values: Vec<isize>) -> isize {
+        let zero_index = values.iter().position(|x| *x == 0).unwrap();
+        [1000, 2000, 3000].iter().map(|look_at| {
+            values[(zero_index + look_at) % values.len()]
+        }).sum()
+    }
+
+    /// Moves every number by shifting the positions of the numbers it
+    /// jumps over, `indexes[i]` is the position of the i-th number.
+    fn mix_numbers(&self, file: &[isize], mut indexes: Vec<usize>) -> Vec<usize> {
+        let cycle = file.len() as isize - 1;
+        for (i, x) in file.iter().enumerate() {
+            if *x == 0 {
+                continue;
+            }
+            let current_index = indexes[i];
+            let new_index = if x.is_positive() {
+                let temp_index = current_index + (*x % cycle) as usize;
+                if temp_index > cycle as usize { temp_index % cycle as usize } else { temp_index }
+            } else {
+                let temp_index = (current_index as isize + *x) % cycle;
+                if temp_index <= 0 { (cycle + temp_index) as usize } else { temp_index as usize }
+            };
+            if new_index == current_index {
+                continue;
+            }
+            let (min, max, add) = if current_index < new_index {
+                (current_index, new_index, -1)
+            } else {
+                (new_index, current_index, 1)
+            };
+            indexes.iter_mut().filter(|ni| {
+                (min..=max).contains(*ni)
+            }).for_each(|ni| {
+                *ni = (*ni as isize + add) as usize;
+            });
+            indexes[i] = new_index;
+        }
+        indexes
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Advent;
+
+    #[test]
+    fn test_matches_default() {
+        let data = include_str!("example.txt");
+        let params = crate::Params::default();
+        let index_shift = GrovePositioningSystem::new(data, &params);
+        let default = super::super::GrovePositioningSystem::new(data, &params);
+        assert_eq!(index_shift.part_01(), default.part_01());
+        assert_eq!(index_shift.part_02(), default.part_02());
+    }
+}
@@ -1,23 +1,24 @@
 // Upgraded my solution from AxlLind solution from
 // https://github.com/AxlLind/AdventOfCode2022/blob/main/src/bin/20.rs
 
+pub mod index_shift;
 
-pub struct GrovePositioningSystem {    
+pub struct GrovePositioningSystem {
     file: Vec<isize>
 }
 
 impl crate::Advent for GrovePositioningSystem {
-    fn new(data: &str) -> Self
-        where 
+    fn new(data: &str, _params: &crate::Params) -> Self
+        where
             Self: Sized {
         let file = data.lines().map(|l| {
             l.parse().unwrap()
         }).collect();
         Self { file }
     }
-    
-    fn part_01(&self) -> String {  
-        let mut indexes:Vec<usize> = (0..self.file.len()).collect();  
+
+    fn part_01(&self) -> String {
+        let mut indexes = BlockList::new(self.file.len());
         self.mix_numbers(&self.file, &mut indexes);
         let result = self.find_groove_coordinates(&self.file, &indexes);
         result.to_string()
@@ -26,7 +27,7 @@ impl crate::Advent for GrovePositioningSystem {
     fn part_02(&self) -> String {
         let key = 811589153;
         let file: Vec<isize> = self.file.iter().map(|n| *n * key).collect();
-        let mut indexes:Vec<usize> = (0..file.len()).collect();
+        let mut indexes = BlockList::new(file.len());
         for _ in 0..10 {
             self.mix_numbers(&file, &mut indexes);
         }
@@ -36,15 +37,118 @@ impl crate::Advent for GrovePositioningSystem {
 }
 
 impl GrovePositioningSystem {
-    fn find_groove_coordinates(&self, file: &Vec<isize>, indexes: &Vec<usize>) -> isize {
+    fn find_groove_coordinates(&self, file: &[isize], indexes: &BlockList) -> isize {
         let original_zero_i = file.iter().position(|&i| i == 0).unwrap();
-        let zero_i = indexes.iter().position(|&i| i == original_zero_i).unwrap();
+        let zero_i = indexes.position(original_zero_i);
         [1000, 2000, 3000].iter().map(|i| {
-            file[indexes[(zero_i + i) % indexes.len()]]
+            file[indexes.get((zero_i + i) % indexes.len())]
         }).sum()
     }
 
-    fn mix_numbers(&self, file: &Vec<isize>, indexes: &mut Vec<usize>) {
+    fn mix_numbers(&self, file: &[isize], indexes: &mut BlockList) {
+        if file.len() < 2 {
+            return;
+        }
+        for (i, &x) in file.iter().enumerate() {
+            let pos = indexes.remove(i);
+            let new_index = (pos as isize + x).rem_euclid(file.len() as isize - 1) as usize;
+            indexes.insert(new_index, i);
+        }
+    }
+}
+
+/// Order of the ids `0..n` split into blocks of about `sqrt(n)` ids, so
+/// finding, removing and inserting an id all take `O(sqrt(n))`.
+struct BlockList {
+    blocks: Vec<Vec<usize>>,
+    // Block holding every id
+    block_of: Vec<usize>,
+    block_size: usize,
+    len: usize
+}
+
+impl BlockList {
+    fn new(n: usize) -> Self {
+        let mut list = BlockList {
+            blocks: vec![(0..n).collect()],
+            block_of: vec![0; n],
+            block_size: (n as f64).sqrt().ceil().max(1.0) as usize,
+            len: n
+        };
+        list.rebuild();
+        list
+    }
+
+    /// Splits the ids back into blocks of equal size.
+    fn rebuild(&mut self) {
+        let ids: Vec<usize> = self.blocks.iter().flatten().copied().collect();
+        self.blocks = ids.chunks(self.block_size).map(|c| c.to_vec()).collect();
+        for (b, block) in self.blocks.iter().enumerate() {
+            for id in block {
+                self.block_of[*id] = b;
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn position(&self, id: usize) -> usize {
+        let b = self.block_of[id];
+        let before: usize = self.blocks[..b].iter().map(Vec::len).sum();
+        before + self.blocks[b].iter().position(|&i| i == id).unwrap()
+    }
+
+    fn get(&self, mut position: usize) -> usize {
+        for block in self.blocks.iter() {
+            if position < block.len() {
+                return block[position];
+            }
+            position -= block.len();
+        }
+        panic!("Position {} out of bounds", position)
+    }
+
+    /// Takes the id out of the list, returns the position it was at.
+    fn remove(&mut self, id: usize) -> usize {
+        let position = self.position(id);
+        let block = &mut self.blocks[self.block_of[id]];
+        let i = block.iter().position(|&i| i == id).unwrap();
+        block.remove(i);
+        self.len -= 1;
+        position
+    }
+
+    fn insert(&mut self, mut position: usize, id: usize) {
+        let last = self.blocks.len() - 1;
+        let b = (0..=last).find(|&b| {
+            let len = self.blocks[b].len();
+            if position <= len {
+                true
+            } else {
+                position -= len;
+                false
+            }
+        }).unwrap_or_else(|| panic!("Position out of bounds"));
+        self.blocks[b].insert(position, id);
+        self.block_of[id] = b;
+        self.len += 1;
+        if self.blocks[b].len() > 2 * self.block_size {
+            self.rebuild();
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Advent;
+    use std::time::Instant;
+
+    /// The former mixing, a `Vec` with a linear search for every number.
+    fn mix_vec(file: &[isize], indexes: &mut Vec<usize>) {
         for (i, &x) in file.iter().enumerate() {
             let pos = indexes.iter().position(|&n| n == i).unwrap();
             indexes.remove(pos);
@@ -52,4 +156,64 @@ impl GrovePositioningSystem {
             indexes.insert(new_index, i);
         }
     }
-}
\ No newline at end of file
+
+    fn generate(n: usize) -> Vec<isize> {
+        // Linear congruential generator, big enough numbers to wrap many times
+        let mut seed: u64 = 20;
+        let mut file: Vec<isize> = (0..n).map(|_| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) as isize % 20000 - 10000
+        }).collect();
+        file[n / 2] = 0;
+        file
+    }
+
+    #[test]
+    fn test_block_list() {
+        let mut list = BlockList::new(10);
+        assert_eq!(list.remove(3), 3);
+        list.insert(0, 3);
+        assert_eq!((list.position(3), list.position(0), list.get(9)), (0, 1, 9));
+        for _ in 0..20 {
+            let position = list.remove(9);
+            list.insert((position + 1) % list.len(), 9);
+        }
+        let order: Vec<usize> = (0..10).map(|p| list.get(p)).collect();
+        let mut sorted = order.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_matches_vec() {
+        let grove = GrovePositioningSystem::new(include_str!("example.txt"), &crate::Params::default());
+        assert_eq!((grove.part_01(), grove.part_02()), ("3".to_string(), "1623178306".to_string()));
+        let file = generate(2000);
+        let mut vec: Vec<usize> = (0..file.len()).collect();
+        mix_vec(&file, &mut vec);
+        let mut list = BlockList::new(file.len());
+        grove.mix_numbers(&file, &mut list);
+        let vec_zero = vec.iter().position(|&i| i == file.len() / 2).unwrap();
+        let list_zero = list.position(file.len() / 2);
+        for k in 0..file.len() {
+            assert_eq!(vec[(vec_zero + k) % file.len()], list.get((list_zero + k) % file.len()));
+        }
+    }
+
+    // cargo test --release bench_mixing -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_mixing() {
+        let grove = GrovePositioningSystem { file: vec![] };
+        for n in [5_000, 20_000, 100_000] {
+            let file = generate(n);
+            let start = Instant::now();
+            grove.mix_numbers(&file, &mut BlockList::new(n));
+            let block_list = start.elapsed();
+            let start = Instant::now();
+            mix_vec(&file, &mut (0..n).collect());
+            let vec = start.elapsed();
+            println!("{} numbers: block list {:?}, vec {:?}", n, block_list, vec);
+        }
+    }
+}
@@ -1,171 +1,241 @@
 use std::collections::HashMap;
+use std::str::FromStr;
+use anyhow::{Result, Error, Context, anyhow, bail};
+use num::rational::Ratio;
+use num::{Zero, One};
+use crate::utils::parse;
+
+type Rational = Ratio<i128>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div
+}
 
+impl FromStr for Operator {
+    type Err = Error;
 
-pub struct MonkeyMath {
-    monkeys: HashMap<String, MonkeySay>
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "+" => Ok(Self::Add),
+            "-" => Ok(Self::Sub),
+            "*" => Ok(Self::Mul),
+            "/" => Ok(Self::Div),
+            _ => Err(anyhow!("Invalid operator {}", s))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Job {
+    Value(Rational),
+    Operation(usize, Operator, usize)
 }
 
-// 1705 -- too low
-#[derive(Clone, Debug)]
-enum MonkeySay {
-    Value(isize),
-    Expression((String, char, String))
+/// `a * x + b` where `x` is the unknown being solved for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Linear {
+    a: Rational,
+    b: Rational
 }
 
-fn add(a: isize, b: isize) -> isize { a + b }
-fn sub(a: isize, b: isize) -> isize { a - b }
-fn div(a: isize, b: isize) -> isize { a / b }
-fn mul(a: isize, b: isize) -> isize { a * b }
+impl Linear {
+    fn constant(b: Rational) -> Self {
+        Linear { a: Rational::zero(), b }
+    }
+
+    fn unknown() -> Self {
+        Linear { a: Rational::one(), b: Rational::zero() }
+    }
 
-fn get_operation(operator: char) -> impl Fn(isize, isize) -> isize {
-    match operator {
-        '+' => add,
-        '-' => sub,
-        '/' => div,
-        '*' => mul,
-        _ => panic!("Invalid operator")
+    fn is_constant(&self) -> bool {
+        self.a.is_zero()
+    }
+
+    fn apply(&self, operator: Operator, other: &Linear) -> Result<Linear> {
+        Ok(match operator {
+            Operator::Add => Linear { a: self.a + other.a, b: self.b + other.b },
+            Operator::Sub => Linear { a: self.a - other.a, b: self.b - other.b },
+            Operator::Mul => {
+                if !self.is_constant() && !other.is_constant() {
+                    bail!("Equation is nonlinear, the unknown gets multiplied by itself");
+                }
+                Linear {
+                    a: self.a * other.b + other.a * self.b,
+                    b: self.b * other.b
+                }
+            },
+            Operator::Div => {
+                if !other.is_constant() {
+                    bail!("Equation is nonlinear, the unknown is in a divisor");
+                }
+                if other.b.is_zero() {
+                    bail!("Division by zero");
+                }
+                Linear { a: self.a / other.b, b: self.b / other.b }
+            }
+        })
     }
 }
 
-#[derive(Debug)]
-enum Equation {
-    Name(String),
-    Value(isize),
-    Expression((Box<Equation>, char, Box<Equation>))
+/// Monkeys form a DAG, every monkey either yells a number or combines the
+/// numbers of two other monkeys.
+pub struct MonkeyMath {
+    names: HashMap<String, usize>,
+    jobs: Vec<Job>
 }
 
 impl crate::Advent for MonkeyMath {
-    fn new(data: &str) -> Self
-        where 
+    fn new(data: &str, _params: &crate::Params) -> Self
+        where
             Self: Sized {
-        let monkeys = data.lines().map(|l| {
-            let (monkey_name, expression_str) = l.split_once(": ").unwrap();
-
-            let expression: Result<isize, _> = expression_str.parse();
-            let expression = match expression {
-                Ok(val) => MonkeySay::Value(val),
-                Err(_) => {
-                    let mut it = expression_str.split(" ");
-                    let name1 = it.next().unwrap().to_owned();
-                    let operator = it.next().unwrap().chars().next().unwrap();
-                    let name2 = it.next().unwrap().to_owned();
-                    MonkeySay::Expression((name1, operator, name2))
-                }
-            };
-            (monkey_name.to_owned(), expression)
-        }).collect();
-        Self { monkeys }
+        Self::parse(data).unwrap()
     }
 
     fn part_01(&self) -> String {
-        let result = evaluate_expression(&self.monkeys, "root");        
-        result.to_string()
+        self.evaluate("root").unwrap().to_string()
     }
 
     fn part_02(&self) -> String {
-        let mut humn_path : Vec<Equation> = vec![];
-        get_equation_for_humn(&self.monkeys, "root", &mut humn_path);
-        
-        let final_step = humn_path.pop().unwrap();
-        let target = match final_step {
-            Equation::Expression((op1, _, op2)) => {
-                match (*op1, *op2) {
-                    (Equation::Value(val), _)  => val,
-                    (_, Equation::Value(val)) => val,
-                    _ => unreachable!()
-                }
-            },
-            _ => unreachable!()
-        };
-
-        let result = solve_equation(target, humn_path);
-        result.to_string()
+        self.solve("root", "humn").unwrap().to_string()
     }
 }
 
-fn evaluate_expression(monkeys: &HashMap<String, MonkeySay>, monkey_name: &str) -> isize {
-    match &monkeys[monkey_name] {
-        MonkeySay::Value(val) => *val,
-        MonkeySay::Expression((name1, operator, name2)) => {
-            let operation = get_operation(*operator);
+impl MonkeyMath {
+    fn parse(data: &str) -> Result<Self> {
+        let entries: Vec<(&str, &str)> = parse::lines(data, |l| {
+            let [name, job] = parse::template("{}: {}", l)?;
+            Ok((name, job))
+        })?;
+        let names: HashMap<String, usize> = entries.iter()
+            .enumerate()
+            .map(|(i, (name, _))| (name.to_string(), i))
+            .collect();
+        let id = |name: &str| {
+            names.get(name).copied().ok_or_else(|| anyhow!("Unknown monkey {}", name))
+        };
+        let jobs = entries.iter().map(|(name, job)| {
+            if let Ok(value) = job.parse::<i128>() {
+                return Ok(Job::Value(Rational::from_integer(value)));
+            }
+            let [lhs, operator, rhs] = parse::template("{} {} {}", job)
+                .with_context(|| format!("Invalid job of monkey {}", name))?;
+            Ok(Job::Operation(id(lhs)?, operator.parse()?, id(rhs)?))
+        }).collect::<Result<_>>()?;
+        Ok(MonkeyMath { names, jobs })
+    }
 
-            operation(
-                evaluate_expression(monkeys, name1),
-                evaluate_expression(monkeys, name2)
-            )
-        }
+    fn id(&self, name: &str) -> Result<usize> {
+        self.names.get(name).copied().ok_or_else(|| anyhow!("Unknown monkey {}", name))
     }
-}
 
-fn get_equation_for_humn(monkeys: &HashMap<String, MonkeySay>, monkey_name: &str, humn_equation: &mut Vec<Equation>) -> Option<isize> {
-    if monkey_name == "humn" {
-        return None;
-    }
-    match &monkeys[monkey_name] {
-        MonkeySay::Value(val) => Some(*val),
-        MonkeySay::Expression((name1, operator, name2)) => {
-            let lhs = get_equation_for_humn(monkeys, name1, humn_equation);
-            let rhs = get_equation_for_humn(monkeys, name2, humn_equation);
-            let operation = get_operation(*operator);
-            match (lhs, rhs) {
-                (Some(lhs), Some(rhs)) => Some(operation(lhs, rhs)),
-                (None, Some(rhs)) => {
-                    humn_equation.push(
-                        Equation::Expression((
-                            Box::new(Equation::Name(name1.clone())),
-                            *operator,
-                            Box::new(Equation::Value(rhs))
-                        ))
-                    );
-                    None
-                },
-                (Some(lhs), None) => {
-                    humn_equation.push(
-                        Equation::Expression((
-                            Box::new(Equation::Value(lhs)),
-                            *operator,                            
-                            Box::new(Equation::Name(name2.clone())),
-                        ))
-                    );
-                    None
-                },
-                _ => unreachable!()
+    /// Evaluates every monkey as a linear function of `unknown`, each monkey
+    /// is computed only once even if several monkeys listen to it.
+    fn linear(&self, id: usize, unknown: Option<usize>, memo: &mut [Option<Linear>]) -> Result<Linear> {
+        // Explicit stack, the monkey chains are too deep for recursion
+        let mut stack = vec![id];
+        while let Some(&current) = stack.last() {
+            if memo[current].is_some() {
+                stack.pop();
+                continue;
+            }
+            if Some(current) == unknown {
+                memo[current] = Some(Linear::unknown());
+                continue;
+            }
+            match &self.jobs[current] {
+                Job::Value(value) => memo[current] = Some(Linear::constant(*value)),
+                Job::Operation(lhs, operator, rhs) => {
+                    match (memo[*lhs], memo[*rhs]) {
+                        (Some(l), Some(r)) => memo[current] = Some(l.apply(*operator, &r)?),
+                        (l, r) => {
+                            // Every level of a path through a DAG adds at most two monkeys
+                            if stack.len() > 2 * self.jobs.len() {
+                                bail!("Monkeys listen to each other in a loop");
+                            }
+                            if l.is_none() { stack.push(*lhs); }
+                            if r.is_none() { stack.push(*rhs); }
+                        }
+                    }
+                }
             }
         }
+        Ok(memo[id].unwrap())
+    }
+
+    fn evaluate(&self, name: &str) -> Result<Rational> {
+        let mut memo = vec![None; self.jobs.len()];
+        Ok(self.linear(self.id(name)?, None, &mut memo)?.b)
+    }
+
+    /// Value of `unknown` for which both sides of the `root` operation are
+    /// equal.
+    fn solve(&self, root: &str, unknown: &str) -> Result<Rational> {
+        let (lhs, rhs) = match self.jobs[self.id(root)?] {
+            Job::Operation(lhs, _, rhs) => (lhs, rhs),
+            Job::Value(_) => bail!("Monkey {} doesn't compare two monkeys", root)
+        };
+        let unknown = self.id(unknown)?;
+        let mut memo = vec![None; self.jobs.len()];
+        let lhs = self.linear(lhs, Some(unknown), &mut memo)?;
+        let rhs = self.linear(rhs, Some(unknown), &mut memo)?;
+        let (side, target) = match (lhs.is_constant(), rhs.is_constant()) {
+            (false, true) => (lhs, rhs.b),
+            (true, false) => (rhs, lhs.b),
+            (false, false) => bail!("Unknown appears on both sides of the equation"),
+            (true, true) => bail!("Equation doesn't depend on the unknown")
+        };
+        Ok((target - side.b) / side.a)
     }
 }
 
-fn solve_equation(target: isize, humn_path: Vec<Equation>) -> isize {
-    let mut result = target;
-    for step in humn_path.into_iter().rev() {
-        match step {
-            Equation::Expression((lhs, operator, rhs)) => {
-                result = match (*lhs, *rhs) {
-                    // Reversing math operations
-                    (Equation::Value(val), _) => {
-                        match operator {
-                            '+' => sub(result, val),
-                            '-' => sub(val, result),
-                            '*' => div(result, val),
-                            '/' => div(val, result),
-                            _ => unreachable!()
-                        }                            
-                    },
-                    (_, Equation::Value(val)) => {
-                        match operator {
-                            '+' => sub(result, val),
-                            '-' => add(result, val),
-                            '*' => div(result, val),
-                            '/' => mul(result, val),
-                            _ => unreachable!()
 
-                        }
-                    },
-                    _ => unreachable!()
-                };
-            },
-            _ => unreachable!()
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+root: pppw + sjmn
+dbpl: 5
+cczh: sllz + lgvd
+zczc: 2
+ptdq: humn - dvpt
+dvpt: 3
+lfqf: 4
+humn: 5
+ljgn: 2
+sjmn: drzm * dbpl
+sllz: 4
+pppw: cczh / lfqf
+lgvd: ljgn * ptdq
+drzm: hmdt - zczc
+hmdt: 32";
+
+    #[test]
+    fn test_example() {
+        let monkeys = MonkeyMath::parse(EXAMPLE).unwrap();
+        assert_eq!(monkeys.evaluate("root").unwrap(), Rational::from_integer(152));
+        assert_eq!(monkeys.solve("root", "humn").unwrap(), Rational::from_integer(301));
+        // Any monkey can be the unknown
+        assert_eq!(monkeys.solve("root", "dvpt").unwrap(), Rational::from_integer(-293));
     }
-    result
-}
\ No newline at end of file
+
+    #[test]
+    fn test_exact_division() {
+        let monkeys = MonkeyMath::parse("root: a / b\na: 7\nb: 2").unwrap();
+        assert_eq!(monkeys.evaluate("root").unwrap(), Rational::new(7, 2));
+    }
+
+    #[test]
+    fn test_unsolvable() {
+        let both_sides = MonkeyMath::parse("root: a + x\na: x * b\nb: 2\nx: 1").unwrap();
+        assert!(both_sides.solve("root", "x").is_err());
+        let nonlinear = MonkeyMath::parse("root: a + b\na: x * x\nb: 2\nx: 1").unwrap();
+        assert!(nonlinear.solve("root", "x").is_err());
+        let divisor = MonkeyMath::parse("root: a + b\na: b / x\nb: 2\nx: 1").unwrap();
+        assert!(divisor.solve("root", "x").is_err());
+    }
+}
@@ -1,184 +1,188 @@
 use crate::utils::point::{Coord, Direction, DIRECTIONS};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use core::fmt;
 
 type C = Coord<i32>;
 pub struct UnstableDiffusion {
-    elfs: Vec<C>
+    elfs: HashSet<C>
 }
 
 impl crate::Advent for UnstableDiffusion {
-    fn new(data: &str) -> Self
-        where 
+    fn new(data: &str, _params: &crate::Params) -> Self
+        where
             Self: Sized {
         let height = data.lines().count() as i32;
-        let elfs: Vec<C> = data.lines().enumerate().map(|(y, l)| {
-            l.chars().enumerate().filter_map(|(x, c)| {
+        let elfs: HashSet<C> = data.lines().enumerate().flat_map(|(y, l)| {
+            l.chars().enumerate().filter_map(move |(x, c)| {
                 match c {
                     '#' => Some(C::new(x as i32, height - 1 - y as i32)),
                     _ => None
                 }
-            }).collect::<Vec<_>>()
-        }).flatten()
-        .collect();    
+            })
+        }).collect();
         Self { elfs }
     }
 
     fn part_01(&self) -> String {
-        let (_rounds, elfs) = self.move_elfs(Some(10));
-        UnstableDiffusion::count_empty(&elfs).to_string()
+        let mut rounds = self.rounds();
+        rounds.nth(9);
+        rounds.positions().count_empty().to_string()
     }
 
     fn part_02(&self) -> String {
-        let (rounds, _elfs) = self.move_elfs(None);
-        (rounds + 1).to_string()
+        let round = self.rounds().find(|r| r.moved == 0).unwrap();
+        round.number.to_string()
     }
 }
 
 impl UnstableDiffusion {
-    #[allow(dead_code)]
-    fn display_elfs(elfs: &Vec<C>) {
-        let mut max_x = i32::MIN;
-        let mut min_x = 0;        
-        let mut max_y = i32::MIN;
-        let mut min_y = 0;
-        for coord in elfs {
-            if coord.x < min_x {
-                min_x = coord.x;
-            } 
-            if coord.x > max_x {
-                max_x = coord.x;
+    /// Every round of the diffusion, it never ends on its own, the elves
+    /// just stop moving.
+    fn rounds(&self) -> Rounds {
+        Rounds { elfs: self.elfs.clone(), number: 0 }
+    }
+}
+
+const DIRECTION_ORDER: [Direction; 4] = [Direction::N, Direction::S, Direction::W, Direction::E];
+
+/// Directions an elf has to see empty to move towards `direction`.
+fn facing(direction: &Direction) -> [Direction; 3] {
+    match direction {
+        Direction::N => [Direction::NW, Direction::N, Direction::NE],
+        Direction::S => [Direction::SE, Direction::S, Direction::SW],
+        Direction::E => [Direction::NE, Direction::E, Direction::SE],
+        Direction::W => [Direction::SW, Direction::W, Direction::NW],
+        _ => panic!("Elves only move straight, not {:?}", direction)
+    }
+}
+
+/// Bits of [`facing`] in the order of `DIRECTIONS`.
+fn facing_mask(direction: &Direction) -> u8 {
+    facing(direction).iter()
+        .map(|f| 1 << DIRECTIONS.iter().position(|d| d == f).unwrap())
+        .fold(0, |mask, bit| mask | bit)
+}
+
+struct Rounds {
+    elfs: HashSet<C>,
+    number: usize
+}
+
+impl Iterator for Rounds {
+    type Item = Round;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Target of every proposal, `None` once two elves propose it
+        let mut proposals: HashMap<C, Option<C>> = HashMap::new();
+        for elf in self.elfs.iter() {
+            // Looks every neighbour up once, bit `i` is `DIRECTIONS[i]`
+            let neighbours = DIRECTIONS.iter()
+                .enumerate()
+                .filter(|(_, d)| self.elfs.contains(&elf.get_neighbour(d)))
+                .fold(0u8, |mask, (i, _)| mask | 1 << i);
+            if neighbours == 0 {
+                continue;
             }
-            if coord.y < min_y {
-                min_y = coord.y;
-            } 
-            if coord.y > max_y {
-                max_y = coord.y;
-            }            
-        }
-        println!("X: {} -> {}, Y: {} -> {}", min_x, max_x, min_y, max_y);
-
-        for y in (min_y..=max_y).rev() {
-            let mut row: String = String::new();
-            for x in min_x..=max_x {                
-                if elfs.iter().any(|c: &Coord<i32>| c.x == x && c.y == y) {
-                    row.push('#');
-                } else {
-                    row.push('.');
+            let direction = DIRECTION_ORDER.iter()
+                .cycle()
+                .skip(self.number % DIRECTION_ORDER.len())
+                .take(DIRECTION_ORDER.len())
+                .find(|d| neighbours & facing_mask(d) == 0);
+            if let Some(direction) = direction {
+                match proposals.entry(elf.get_neighbour(direction)) {
+                    Entry::Occupied(mut e) => { e.insert(None); },
+                    Entry::Vacant(e) => { e.insert(Some(*elf)); }
                 }
-                
             }
-            println!("{}", row);
         }
-    }
 
-    fn count_empty(elfs: &Vec<C>) -> usize {
-        let mut max_x = i32::MIN;
-        let mut min_x = i32::MAX;        
-        let mut max_y = i32::MIN;
-        let mut min_y = i32::MAX;
-        for coord in elfs {
-            if coord.x < min_x {
-                min_x = coord.x;
-            } 
-            if coord.x > max_x {
-                max_x = coord.x;
-            }
-            if coord.y < min_y {
-                min_y = coord.y;
-            } 
-            if coord.y > max_y {
-                max_y = coord.y;
-            }            
-        }
-        let mut empty_counter = 0;
-        for y in (min_y..=max_y).rev() {
-            for x in min_x..=max_x {                
-                if !elfs.iter().any(|c| c.x == x && c.y == y) {
-                    empty_counter += 1;
-                }                
+        let mut moved = 0;
+        for (target, elf) in proposals {
+            if let Some(elf) = elf {
+                self.elfs.remove(&elf);
+                self.elfs.insert(target);
+                moved += 1;
             }
         }
+        self.number += 1;
+        Some(Round { number: self.number, moved })
+    }
+}
 
-        empty_counter
+impl Rounds {
+    /// Elves after the last round.
+    fn positions(&self) -> Positions<'_> {
+        Positions(&self.elfs)
     }
+}
 
-    fn move_elfs(&self, round_limit: Option<usize>) -> (usize, Vec<C>) {
-        const DIRECTION_ORDER: [Direction; 4] = [Direction::N, Direction::S, Direction::W, Direction::E];
-        let mut elfs = self.elfs.clone();
-        let mut i = 0;
-        'move_elfs: loop {
-            match round_limit {
-                Some(rl) => if i >= rl { break; },
-                None => {}
-            }
-            let dir_pos = i % DIRECTION_ORDER.len();
-            // println!("{} -- Elfs: {:?}", i, elfs);
-            // UnstableDiffusion::display_elfs(&elfs);
-            let mut moves: HashMap<usize, Direction> = HashMap::new();
-            'check_moves: for (elf_i, elf) in elfs.iter().enumerate() {
-                let neigbour_elfs: Vec<Option<&C>> = DIRECTIONS
-                    .iter()
-                    .map(|direction| {
-                        let nc = elf.get_neighbour(direction);
-                        elfs.iter().filter(|c| **c == nc).next()
-                    }).collect();                
-    
-                // No need to move if all spaces are empty 
-                if neigbour_elfs.iter().all(|n| n.is_none()) {
-                    continue 'check_moves;
-                }
-                'search_direction: for new_dir in DIRECTION_ORDER.iter().cycle().skip(dir_pos).take(DIRECTION_ORDER.len()) {
-                    let facing_spaces: [Option<&C>; 3] = match new_dir {
-                        Direction::N => {[neigbour_elfs[7], neigbour_elfs[0], neigbour_elfs[1]]},
-                        Direction::S => {[neigbour_elfs[3], neigbour_elfs[4], neigbour_elfs[5]]},
-                        Direction::E => {[neigbour_elfs[1], neigbour_elfs[2], neigbour_elfs[3]]},
-                        Direction::W => {[neigbour_elfs[5], neigbour_elfs[6], neigbour_elfs[7]]},
-                        _ => panic!("Invalid dir {:?}", dir_pos)
-                    };
-                    if facing_spaces.iter().all(|s| s.is_none()) {
-                        moves.insert(elf_i, *new_dir);                        
-                        break 'search_direction;
-                    }
-                }
-            }
+/// Summary of a round, the first round is 1.
+#[derive(Debug, Clone, Copy)]
+struct Round {
+    number: usize,
+    moved: usize
+}
 
-            if moves.len() == 0 {
-                break 'move_elfs;
-            }
+/// Borrowed view of where the elves are.
+struct Positions<'a>(&'a HashSet<C>);
 
-            let mut moves_to: HashMap<usize, C> = HashMap::with_capacity(moves.len());
-            for (elf_i, new_dir) in moves.iter() {
-                let elf = elfs.get_mut(*elf_i).unwrap();
-                let new_coord = elf.get_neighbour(new_dir);
-                moves_to.insert(*elf_i, new_coord);
-            }
+impl Positions<'_> {
+    /// Smallest rectangle containing every elf as `(min, max)` corners.
+    fn bounds(&self) -> (C, C) {
+        let xs = self.0.iter().map(|c| c.x);
+        let ys = self.0.iter().map(|c| c.y);
+        (
+            C::new(xs.clone().min().unwrap_or(0), ys.clone().min().unwrap_or(0)),
+            C::new(xs.max().unwrap_or(0), ys.max().unwrap_or(0))
+        )
+    }
 
-            let mut target_count: HashMap<C, usize> = HashMap::with_capacity(moves_to.len());
-            for (_elf_i, target_coord) in moves_to.iter() {
-                target_count.entry(*target_coord).and_modify(|counter| *counter += 1).or_insert(1);
-            }
+    fn count_empty(&self) -> usize {
+        let (min, max) = self.bounds();
+        let area = ((max.x - min.x + 1) * (max.y - min.y + 1)) as usize;
+        area - self.0.len()
+    }
+}
 
-            let duplicated_targets: Vec<C> = target_count
-                .into_iter()
-                .filter_map(|(target_coord, counter)| {
-                    if counter > 1 {
-                        Some(target_coord)
-                    } else {
-                        None
-                    }
-                }).collect();
-
-            moves_to
-                .into_iter()
-                .filter(|(_elf_i, target_coord)| {
-                    !duplicated_targets.contains(target_coord)
-                }).for_each(|(elf_i, target_coord)| {
-                    let elf = elfs.get_mut(elf_i).unwrap();
-                    *elf = target_coord;
-                });
-
-            i += 1;
+impl fmt::Display for Positions<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (min, max) = self.bounds();
+        for y in (min.y..=max.y).rev() {
+            let row: String = (min.x..=max.x)
+                .map(|x| if self.0.contains(&C::new(x, y)) { '#' } else { '.' })
+                .collect();
+            writeln!(f, "{}", row)?;
         }
-        (i, elfs)
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Advent;
+
+    #[test]
+    fn test_small_example() {
+        let diffusion = UnstableDiffusion::new(".....\n..##.\n..#..\n.....\n..##.\n.....", &crate::Params::default());
+        let mut rounds = diffusion.rounds();
+        let mut moved = vec![];
+        let mut frames = vec![];
+        for _ in 0..4 {
+            moved.push(rounds.next().unwrap().moved);
+            frames.push(rounds.positions().to_string());
+        }
+        assert_eq!(frames[0], "##\n..\n#.\n.#\n#.\n");
+        assert_eq!(frames[2], "..#..\n....#\n#....\n....#\n.....\n..#..\n");
+        assert_eq!(moved, vec![3, 5, 3, 0]);
+    }
+
+    #[test]
+    fn test_example() {
+        let diffusion = UnstableDiffusion::new(include_str!("example.txt"), &crate::Params::default());
+        assert_eq!(diffusion.part_01(), "110");
+        assert_eq!(diffusion.part_02(), "20");
+    }
+}
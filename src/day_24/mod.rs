@@ -1,91 +1,309 @@
-
-use crate::utils::point::{Point, Coord, Direction};
+use crate::utils::point::{Point, Coord, Direction, TOUCHING_DIRECTIONS, neighbour_within};
+use crate::utils::parse;
+use anyhow::{Error, Result, anyhow, bail};
+use std::collections::HashSet;
+use std::str::FromStr;
 use core::fmt;
 
 type Blizzard = Point<usize, Direction>;
+type C = Coord<usize>;
 
 pub struct BlizardBasin {
+    // Size of the basin without the walls
     height: usize,
     width: usize,
-    blizzards: Vec<Blizzard>
+    // Entry and exit gaps in map coordinates, walls included
+    entry: C,
+    exit: C,
+    blizzards: Vec<Blizzard>,
+    // Blizzard occupancy of the basin for every minute of a full period
+    occupied: Vec<Vec<bool>>,
+    render: Minutes
 }
 
-impl crate::Advent for BlizardBasin {
-    fn new(data: &str) -> Self
-        where 
-            Self: Sized {        
-        let height = data.len() - 2;
-        let width = data.lines().next().unwrap().len() - 2;
-
-        let blizzards: Vec<Blizzard> = data
-            .lines()
-            .enumerate()
-            .map(|(y, l)| {
-                l.chars()
-                    .enumerate()
-                    .filter_map(|(x, c)| {
-                        let dir = match c {
-                            '<' => Some(Direction::W),
-                            '>' => Some(Direction::E),
-                            '^' => Some(Direction::N),
-                            'v' => Some(Direction::S),
-                            _ => None
-                        };
-                        match dir {
-                            Some(dir) => Some(Point::new(x - 1, y - 1, dir)),
-                            None => None
-                        }
-                    }).collect::<Vec<_>>()
-            }).flatten()
-            .collect();
-        Self {
-            height,
-            width,
-            blizzards
+/// Comma separated minutes, like `0,5,18`, empty for none.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Minutes(Vec<usize>);
+
+impl FromStr for Minutes {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Ok(Minutes(vec![]));
         }
+        Ok(Minutes(s.split(',').map(parse::value).collect::<Result<_>>()?))
+    }
+}
+
+impl crate::Advent for BlizardBasin {
+    fn new(data: &str, params: &crate::Params) -> Self
+        where
+            Self: Sized {
+        let mut basin = Self::parse(data).unwrap();
+        basin.render = params.get("render");
+        basin
+    }
+
+    fn parameters() -> &'static [crate::Parameter] {
+        const PARAMETERS: &[crate::Parameter] = &[
+            crate::Parameter::new::<Minutes>("render", "", ""),
+        ];
+        PARAMETERS
     }
 
     fn part_01(&self) -> String {
-        
-        1.to_string()
+        for minute in self.render.0.iter() {
+            println!("Minute {}:\n{}", minute, self.render(*minute, None));
+        }
+        self.crossing(self.entry, self.exit, 0).unwrap().to_string()
     }
 
     fn part_02(&self) -> String {
-        2.to_string()
+        self.round_trip().unwrap().to_string()
+    }
+}
+
+/// Position of the single gap in a wall row.
+fn wall_gap(line: &str, row: usize) -> Result<usize> {
+    let gaps: Vec<usize> = line.char_indices().filter(|(_, c)| *c != '#').map(|(x, _)| x).collect();
+    match gaps[..] {
+        [x] if line.as_bytes()[x] == b'.' => Ok(x),
+        _ => Err(anyhow!("Wall on line {} has to contain exactly one gap: {}", row + 1, line))
     }
 }
 
 impl BlizardBasin {
-    fn sort_blizzards(&self, &mut mut blizzards: &mut Vec<Blizzard>) {
-        blizzards.sort_unstable_by(|a, b| {
-            a.coord.cmp(&b.coord)
-        });        
+    fn parse(data: &str) -> Result<Self> {
+        let lines: Vec<&str> = data.lines().collect();
+        if lines.len() < 3 {
+            bail!("Basin needs at least one row between the walls");
+        }
+        let map_width = lines[0].len();
+        if map_width < 3 {
+            bail!("Basin needs at least one column between the walls");
+        }
+        let height = lines.len() - 2;
+        let width = map_width - 2;
+
+        let entry = Coord::new(wall_gap(lines[0], 0)?, 0);
+        let exit = Coord::new(wall_gap(lines[height + 1], height + 1)?, height + 1);
+
+        let mut blizzards: Vec<Blizzard> = vec![];
+        for (y, l) in lines.iter().enumerate().skip(1).take(height) {
+            if l.len() != map_width || !l.starts_with('#') || !l.ends_with('#') {
+                bail!("Line {} isn't enclosed by walls: {}", y + 1, l);
+            }
+            for (x, c) in l.chars().enumerate().skip(1).take(width) {
+                match c {
+                    '.' => {},
+                    '<' | '>' | '^' | 'v' => {
+                        let dir = Direction::try_from(c)?;
+                        blizzards.push(Point::new(x - 1, y - 1, dir));
+                    },
+                    _ => bail!("Invalid char {} on line {}", c, y + 1)
+                }
+            }
+        }
+
+        let mut basin = Self {
+            height,
+            width,
+            entry,
+            exit,
+            blizzards,
+            occupied: vec![],
+            render: Minutes::default()
+        };
+        basin.occupied = basin.blizzard_period();
+        Ok(basin)
+    }
+
+    /// Blizzards wrap around on their own, so where they are at any minute
+    /// follows from where they started.
+    fn blizzards_at(&self, minute: usize) -> Vec<Blizzard> {
+        let (dx, dy) = (minute % self.width, minute % self.height);
+        self.blizzards.iter().map(|b| {
+            let (x, y) = (b.coord.x, b.coord.y);
+            let coord = match b.value {
+                Direction::E => Coord::new((x + dx) % self.width, y),
+                Direction::W => Coord::new((x + self.width - dx) % self.width, y),
+                Direction::S => Coord::new(x, (y + dy) % self.height),
+                Direction::N => Coord::new(x, (y + self.height - dy) % self.height),
+                _ => unreachable!("Blizzards only move straight, not {:?}", b.value)
+            };
+            Point::from_coord(coord, b.value)
+        }).collect()
+    }
+
+    fn occupancy(&self, blizzards: &[Blizzard]) -> Vec<bool> {
+        let mut occupied = vec![false; self.width * self.height];
+        for b in blizzards {
+            occupied[b.coord.y * self.width + b.coord.x] = true;
+        }
+        occupied
+    }
+
+    /// Occupancy of every minute until the blizzards are all back where
+    /// they started, which happens after `lcm(width, height)` minutes.
+    fn blizzard_period(&self) -> Vec<Vec<bool>> {
+        let period = num::integer::lcm(self.width, self.height);
+        (0..period).map(|minute| self.occupancy(&self.blizzards_at(minute))).collect()
+    }
+
+    fn is_open(&self, coord: &C, minute: usize) -> bool {
+        if *coord == self.entry || *coord == self.exit {
+            return true;
+        }
+        if coord.x == 0 || coord.y == 0 || coord.x > self.width || coord.y > self.height {
+            return false;
+        }
+        let occupied = &self.occupied[minute % self.occupied.len()];
+        !occupied[(coord.y - 1) * self.width + coord.x - 1]
+    }
+
+    /// Minute at which the expedition reaches `to` when leaving `from` at
+    /// minute `start`.
+    fn crossing(&self, from: C, to: C, start: usize) -> Result<usize> {
+        // Positions repeat with the blizzards, after a full period for every
+        // cell every state has been seen and `to` is out of reach
+        let limit = self.occupied.len() * (self.width * self.height + 2);
+        let mut positions: HashSet<C> = HashSet::from([from]);
+        let mut minute = start;
+        while !positions.contains(&to) {
+            if positions.is_empty() || minute - start > limit {
+                bail!("No way from {} to {} leaving at minute {}", from, to, start);
+            }
+            minute += 1;
+            positions = positions.iter().flat_map(|c| {
+                let moves = TOUCHING_DIRECTIONS.iter().filter_map(|dir| {
+                    neighbour_within(self.height + 2, self.width + 2, c, dir)
+                });
+                std::iter::once(*c).chain(moves)
+            }).filter(|c| self.is_open(c, minute)).collect();
+        }
+        Ok(minute)
+    }
+
+    /// Minute at which the expedition is back at the exit after going there,
+    /// back to the entry for the snacks, and to the exit again.
+    fn round_trip(&self) -> Result<usize> {
+        let there = self.crossing(self.entry, self.exit, 0)?;
+        let back = self.crossing(self.exit, self.entry, there)?;
+        self.crossing(self.entry, self.exit, back)
+    }
+
+    /// The basin as it looks at `minute`, the expedition is drawn as `E`.
+    fn render(&self, minute: usize, expedition: Option<C>) -> String {
+        let blizzards = self.blizzards_at(minute);
+        let mut cells: Vec<Vec<Direction>> = vec![vec![]; self.width * self.height];
+        for b in blizzards.iter() {
+            cells[b.coord.y * self.width + b.coord.x].push(b.value);
+        }
+
+        let mut result = String::new();
+        for y in 0..self.height + 2 {
+            for x in 0..self.width + 2 {
+                let c = Coord::new(x, y);
+                let inside = x > 0 && y > 0 && x <= self.width && y <= self.height;
+                let s = if Some(c) == expedition {
+                    "E".to_string()
+                } else if c == self.entry || c == self.exit {
+                    ".".to_string()
+                } else if !inside {
+                    "#".to_string()
+                } else {
+                    let cell = &cells[(y - 1) * self.width + x - 1];
+                    match cell.len() {
+                        0 => ".".to_string(),
+                        1 => match cell[0] {
+                            Direction::N => "^",
+                            Direction::E => ">",
+                            Direction::S => "v",
+                            _ => "<"
+                        }.to_string(),
+                        n => n.to_string()
+                    }
+                };
+                result.push_str(&s);
+            }
+            result.push('\n');
+        }
+        result
     }
 }
 
 impl fmt::Display for BlizardBasin
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "\n")?;
-        let mut blizzards = self.blizzards.clone();
-        self.sort_blizzards(&mut blizzards);        
-        
-        let mut blizzards_it = blizzards.iter();
-
-        for y in 0..self.height {            
-            for x in 0..self.width {
-                let c = Coord::new(x, y);            
-                let current_coord_blizards: Vec<&Blizzard> = blizzards_it.take_while(|b| {
-                    b.coord == c
-                }).collect();
-                match current_coord_blizards.len() {
-                    0 => write!(f, ".")?,
-                    1 => write!(f, "{}", current_coord_blizards[0].value)?,
-                    n => write!(f, "{}", n)?
-                }
-            }
-            write!(f, "\n")?;
-        }
-        write!(f, "\n")
-    }    
-}
\ No newline at end of file
+        write!(f, "{}", self.render(0, Some(self.entry)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Advent;
+    use std::collections::HashMap;
+
+    const EXAMPLE: &str = "\
+#.######
+#>>.<^<#
+#.<..<<#
+#>v.><>#
+#<^v^^>#
+######.#";
+
+    fn example() -> BlizardBasin {
+        let params = crate::Params::resolve(BlizardBasin::parameters(), true, &HashMap::new()).unwrap();
+        BlizardBasin::new(EXAMPLE, &params)
+    }
+
+    #[test]
+    fn test_example() {
+        let basin = example();
+        assert_eq!(basin.part_01(), "18");
+        assert_eq!(basin.part_02(), "54");
+    }
+
+    #[test]
+    fn test_render() {
+        let basin = example();
+        assert_eq!(basin.render(0, None), format!("{}\n", EXAMPLE));
+        assert_eq!(basin.render(1, Some(Coord::new(1, 1))), "\
+#.######
+#E>3.<.#
+#<..<<.#
+#>2.22.#
+#>v..^<#
+######.#
+");
+    }
+
+    #[test]
+    fn test_invalid_walls() {
+        assert!(BlizardBasin::parse("#..###\n#....#\n####.#").is_err());
+        assert!(BlizardBasin::parse("#.####\n#....#\n######").is_err());
+        assert!(BlizardBasin::parse("#.####\n#....\n####.#").is_err());
+    }
+
+    #[test]
+    fn test_period() {
+        let basin = example();
+        assert_eq!(basin.occupied.len(), 12);
+        assert_eq!(basin.render(12, None), basin.render(0, None));
+        assert_eq!("0, 5,18".parse::<Minutes>().unwrap(), Minutes(vec![0, 5, 18]));
+        assert_eq!("".parse::<Minutes>().unwrap(), Minutes(vec![]));
+        assert!("1,x".parse::<Minutes>().is_err());
+    }
+
+    #[test]
+    fn test_unreachable_exit() {
+        // The blizzard never leaves the only cell of the basin
+        let basin = BlizardBasin::parse("#.#\n#>#\n#.#").unwrap();
+        let error = basin.crossing(basin.entry, basin.exit, 0).unwrap_err();
+        assert_eq!(error.to_string(), "No way from (1, 0) to (1, 2) leaving at minute 0");
+        assert!(basin.round_trip().is_err());
+    }
+}
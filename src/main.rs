@@ -1,4 +1,5 @@
 pub mod utils;
+mod params;
 mod day_01;
 mod day_02;
 mod day_03;
@@ -25,12 +26,14 @@ mod day_23;
 mod day_24;
 
 use std::{error::Error, fs};
+use std::collections::HashMap;
 use structopt::StructOpt;
 use owo_colors::OwoColorize;
 use owo_colors::colors::{Magenta, Cyan};
 use std::time::{Duration, Instant};
 use anyhow::Context;
 use humantime::format_duration;
+use params::{Params, Parameter};
 
 fn get_time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
     let start = Instant::now();
@@ -40,54 +43,119 @@ fn get_time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
     (result, time)
 }
 trait Advent {
-    fn new(data: &str) -> Self
+    fn new(data: &str, params: &Params) -> Self
     where 
         Self: Sized;
+    /// Puzzle constants the day reads from its [`Params`].
+    fn parameters() -> &'static [Parameter]
+    where
+        Self: Sized
+    {
+        &[]
+    }
     fn part_01(&self) -> String;
     fn part_02(&self) -> String;
 }
 
 struct Solution {
     event: Box<dyn Advent>,
+    params: Params,
     time: Duration,
 }
 
 impl Solution {
-    fn new<Event: Advent + 'static>(content: &str) -> Self {
-        let (event, time) = get_time(|| Event::new(content));
+    fn new<Event: Advent + 'static>(content: &str, params: Params) -> Self {
+        let (event, time) = get_time(|| Event::new(content, &params));
 
         Solution {
             event: Box::new(event),
+            params,
             time,
         }
     }
 
-    fn get_result(&self, day: u32) -> Duration {
-        let (part1, time1) = get_time(|| self.event.part_01());
-        let (part2, time2) = get_time(|| self.event.part_02());
-        let time_sum = self.time + time1 + time2;
+    fn answers(&self) -> Answers {
+        let (part_01, time_01) = get_time(|| self.event.part_01());
+        let (part_02, time_02) = get_time(|| self.event.part_02());
+        Answers { part_01, part_02, time: self.time + time_01 + time_02, time_01, time_02 }
+    }
+
+    fn get_result(&self, day: u32, variant: &str) -> Answers {
+        let answers = self.answers();
         println!("--------------------------");
+        let variant = if variant == DEFAULT_VARIANT { String::new() } else { format!(" ({})", variant) };
         println!(
-            "Solution for day {} in {}", 
+            "Solution for day {}{} in {}", 
             day.fg::<Cyan>(),
-            format_duration(time_sum).fg::<Cyan>()
+            variant,
+            format_duration(answers.time).fg::<Cyan>()
         );
         println!(
             "Collected data in {}",
             format_duration(self.time).fg::<Magenta>()    
         );
+        if !self.params.is_empty() {
+            println!("Parameters: {}", self.params);
+        }
         println!(
             "Part 1: {} in {}",
-            part1.fg::<Cyan>(),
-            format_duration(time1).fg::<Magenta>()
+            answers.part_01.fg::<Cyan>(),
+            format_duration(answers.time_01).fg::<Magenta>()
         );
         println!(
             "Part 2: {} in {}",
-            part2.fg::<Cyan>(),
-            format_duration(time2).fg::<Magenta>()
+            answers.part_02.fg::<Cyan>(),
+            format_duration(answers.time_02).fg::<Magenta>()
+        );
+        answers
+    }
+}
+
+struct Answers {
+    part_01: String,
+    part_02: String,
+    // Setup and both parts
+    time: Duration,
+    time_01: Duration,
+    time_02: Duration,
+}
+
+impl Answers {
+    fn part(&self, part: u8) -> &str {
+        if part == 1 { &self.part_01 } else { &self.part_02 }
+    }
+}
+
+/// Compares the answers of every variant of a day, returns whether they all
+/// agree.
+fn cross_check(results: &[(&str, Answers)]) -> bool {
+    let fastest = results.iter().map(|(_, a)| a.time).min().unwrap_or_default();
+    println!("--------------------------");
+    println!("Cross-check of {} variants", results.len());
+    for (name, answers) in results {
+        let ratio = answers.time.as_secs_f64() / fastest.as_secs_f64().max(f64::EPSILON);
+        println!(
+            "{}: {} (x{:.2})",
+            name,
+            format_duration(answers.time).fg::<Magenta>(),
+            ratio
         );
-        time_sum
     }
+    let mut agree = true;
+    for part in [1, 2] {
+        let (_, reference) = &results[0];
+        if results.iter().any(|(_, a)| a.part(part) != reference.part(part)) {
+            agree = false;
+            let answers: Vec<String> = results.iter()
+                .map(|(name, a)| format!("{}={}", name, a.part(part)))
+                .collect();
+            println!("Part {} disagrees: {}", part, answers.join(", ").fg::<Cyan>());
+        }
+    }
+    if agree {
+        println!("All variants agree");
+    }
+    agree
 }
 
 
@@ -98,6 +166,131 @@ struct Cli {
     #[structopt(short, long, help = "Uses example file provided by AOC")]
     example: bool,
 
+    #[structopt(
+        short,
+        long = "param",
+        parse(try_from_str = params::parse_override),
+        help = "Overrides a puzzle parameter, e.g. --param rounds=20"
+    )]
+    params: Vec<(String, String)>,
+
+    #[structopt(
+        short,
+        long = "aux",
+        parse(try_from_str = params::parse_override),
+        help = "Replaces auxiliary data of a day with a file, e.g. --aux rock_shapes=shapes.txt"
+    )]
+    aux: Vec<(String, String)>,
+
+    #[structopt(long, help = "Runs a named alternative solver of a day instead of the default one")]
+    variant: Option<String>,
+
+    #[structopt(long, help = "Runs every solver variant of a day and compares their answers")]
+    cross_check: bool,
+
+    #[structopt(long, help = "Includes slow variants in --cross-check")]
+    slow: bool,
+}
+
+const DEFAULT_VARIANT: &str = "default";
+
+/// Named solver of a day.
+struct Variant {
+    name: &'static str,
+    parameters: fn() -> &'static [Parameter],
+    solve: fn(&str, Params) -> Solution,
+    // Too slow for the real input to run in every cross-check
+    slow: bool,
+}
+
+impl Variant {
+    fn of<Event: Advent + 'static>(name: &'static str) -> Self {
+        Variant {
+            name,
+            parameters: Event::parameters,
+            solve: Solution::new::<Event>,
+            slow: false,
+        }
+    }
+}
+
+/// Entry of a day in the registry, the first variant is the default one.
+struct Day {
+    variants: Vec<Variant>,
+    aux: Vec<(&'static str, &'static str)>,
+}
+
+impl Day {
+    fn of<Event: Advent + 'static>() -> Self {
+        Day {
+            variants: vec![Variant::of::<Event>(DEFAULT_VARIANT)],
+            aux: vec![],
+        }
+    }
+
+    /// Registers an alternative solver selectable with `--variant`.
+    fn with_variant<Event: Advent + 'static>(mut self, name: &'static str) -> Self {
+        self.variants.push(Variant::of::<Event>(name));
+        self
+    }
+
+    /// Registers an alternative solver that `--cross-check` skips unless
+    /// `--slow` is given.
+    fn with_slow_variant<Event: Advent + 'static>(mut self, name: &'static str) -> Self {
+        self.variants.push(Variant { slow: true, ..Variant::of::<Event>(name) });
+        self
+    }
+
+    /// Registers data compiled into the binary that the day reads through
+    /// [`Params::aux`].
+    fn with_aux(mut self, name: &'static str, data: &'static str) -> Self {
+        self.aux.push((name, data));
+        self
+    }
+
+    fn variant(&self, name: Option<&str>) -> Option<&Variant> {
+        match name {
+            Some(name) => self.variants.iter().find(|v| v.name == name),
+            None => self.variants.first()
+        }
+    }
+
+    fn parameters(&self) -> impl Iterator<Item = &Parameter> {
+        self.variants.iter().flat_map(|v| (v.parameters)().iter())
+    }
+}
+
+fn get_day(day: u32) -> Day {
+    match day {
+        1 => Day::of::<day_01::CalorieCounting>(),
+        2 => Day::of::<day_02::RockPaperScissors>(),
+        3 => Day::of::<day_03::RucksackReorganization>(),
+        4 => Day::of::<day_04::CampCleanup>(),
+        5 => Day::of::<day_05::SupplyStacks>(),
+        6 => Day::of::<day_06::TuningTrouble>(),
+        7 => Day::of::<day_07::NoSpaceLeftOnDevice>(),
+        8 => Day::of::<day_08::TreeTopTreeHouse>(),
+        9 => Day::of::<day_09::RopeBridge>(),
+        10 => Day::of::<day_10::CathodeRayTube>(),
+        11 => Day::of::<day_11::MonkeyInTheMiddle>(),
+        12 => Day::of::<day_12::HillClimbingAlhorithm>(),
+        13 => Day::of::<day_13::DistressSignal>(),
+        14 => Day::of::<day_14::RegolithReservoir>(),
+        15 => Day::of::<day_15::BeaconExclusionZone>(),
+        16 => Day::of::<day_16::ProboscideaVolcanium>()
+            .with_slow_variant::<day_16::longest_path::ProboscideaVolcanium>("longest_path"),
+        17 => Day::of::<day_17::PyroclasticFlow>()
+            .with_aux("rock_shapes", include_str!("day_17/rock_shapes.txt")),
+        18 => Day::of::<day_18::BoilingBoulders>(),
+        19 => Day::of::<day_19::NotEnoughMinerals>(),
+        20 => Day::of::<day_20::GrovePositioningSystem>()
+            .with_variant::<day_20::index_shift::GrovePositioningSystem>("index_shift"),
+        21 => Day::of::<day_21::MonkeyMath>(),
+        22 => Day::of::<day_22::MonkeyMap>(),
+        23 => Day::of::<day_23::UnstableDiffusion>(),
+        24 => Day::of::<day_24::BlizardBasin>(),
+        _ => unreachable!(),
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -110,44 +303,67 @@ fn main() -> Result<(), Box<dyn Error>> {
     } else {
         1u32..=24u32
     };
+    let overrides: HashMap<String, String> = args.params.into_iter().collect();
+    for key in overrides.keys() {
+        let known = days.clone().any(|day| {
+            get_day(day).parameters().any(|p| p.name == key)
+        });
+        if !known {
+            return Err(format!("Unknown parameter {}", key).into());
+        }
+    }
+    let aux_files: HashMap<String, String> = args.aux.into_iter().collect();
+    for name in aux_files.keys() {
+        let known = days.clone().any(|day| {
+            get_day(day).aux.iter().any(|(n, _)| n == name)
+        });
+        if !known {
+            return Err(format!("Unknown auxiliary data {}", name).into());
+        }
+    }
     let mut duration = Duration::new(0, 0);
+    let mut disagreements = vec![];
 
     for day in days {
         let filename = format!("src/day_{:02}/{}.txt", day, main_file);
 
-        let mut content: &str = &fs::read_to_string(filename)
+        let content = fs::read_to_string(filename)
             .with_context(|| format!("Could not read {} file for day {}", main_file, day))?;
-        content = content.trim_end();
-
-        let solution = match day {
-            1 => Solution::new::<day_01::CalorieCounting>(content),
-            2 => Solution::new::<day_02::RockPaperScissors>(content),
-            3 => Solution::new::<day_03::RucksackReorganization>(content),
-            4 => Solution::new::<day_04::CampCleanup>(content),
-            5 => Solution::new::<day_05::SupplyStacks>(content),
-            6 => Solution::new::<day_06::TuningTrouble>(content),
-            7 => Solution::new::<day_07::NoSpaceLeftOnDevice>(content),
-            8 => Solution::new::<day_08::TreeTopTreeHouse>(content),
-            9 => Solution::new::<day_09::RopeBridge>(content),
-            10 => Solution::new::<day_10::CathodeRayTube>(content),
-            11 => Solution::new::<day_11::MonkeyInTheMiddle>(content),
-            12 => Solution::new::<day_12::HillClimbingAlhorithm>(content),
-            13 => Solution::new::<day_13::DistressSignal>(content),
-            14 => Solution::new::<day_14::RegolithReservoir>(content),
-            15 => Solution::new::<day_15::BeaconExclusionZone>(content),
-            16 => Solution::new::<day_16::ProboscideaVolcanium>(content),
-            17 => Solution::new::<day_17::PyroclasticFlow>(content),
-            18 => Solution::new::<day_18::BoilingBoulders>(content),
-            19 => Solution::new::<day_19::NotEnoughMinerals>(content),
-            20 => Solution::new::<day_20::GrovePositioningSystem>(content),
-            21 => Solution::new::<day_21::MonkeyMath>(content),
-            22 => Solution::new::<day_22::MonkeyMap>(content),
-            23 => Solution::new::<day_23::UnstableDiffusion>(content),
-            24 => Solution::new::<day_24::BlizardBasin>(content),
-            _ => unreachable!(),
-        };
+        let content = utils::input::normalize(&content);
 
-        duration += solution.get_result(day);        
+        let entry = get_day(day);
+        let variants: Vec<&Variant> = if args.cross_check {
+            entry.variants.iter().filter(|v| {
+                if v.slow && !args.slow {
+                    println!("Skipping slow variant {} of day {}, add --slow to run it", v.name, day);
+                }
+                !v.slow || args.slow
+            }).collect()
+        } else {
+            let variant = entry.variant(args.variant.as_deref()).ok_or_else(|| {
+                format!("Unknown variant {} for day {}", args.variant.as_deref().unwrap_or_default(), day)
+            })?;
+            vec![variant]
+        };
+        let mut results = vec![];
+        for variant in variants {
+            let mut params = Params::resolve((variant.parameters)(), args.example, &overrides)?;
+            for (name, data) in entry.aux.iter() {
+                let data = match aux_files.get(*name) {
+                    Some(path) => fs::read_to_string(path)
+                        .with_context(|| format!("Could not read {} file for {}", path, name))?,
+                    None => data.to_string()
+                };
+                params = params.with_aux(name, utils::input::normalize(&data));
+            }
+            let solution = (variant.solve)(&content, params);
+            let answers = solution.get_result(day, variant.name);
+            duration += answers.time;
+            results.push((variant.name, answers));
+        }
+        if args.cross_check && results.len() > 1 && !cross_check(&results) {
+            disagreements.push(day);
+        }
     }
 
     println!("--------------------------");
@@ -157,5 +373,9 @@ fn main() -> Result<(), Box<dyn Error>> {
     );
     println!("--------------------------");
 
+    if !disagreements.is_empty() {
+        let days: Vec<String> = disagreements.iter().map(u32::to_string).collect();
+        return Err(format!("Variants disagree on day {}", days.join(", ")).into());
+    }
     Ok(())
 }
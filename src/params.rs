@@ -0,0 +1,155 @@
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Display};
+use std::str::FromStr;
+
+/// A puzzle constant with separate defaults for the real input and the
+/// example of the day.
+#[derive(Debug, Clone, Copy)]
+pub struct Parameter {
+    pub name: &'static str,
+    pub input: &'static str,
+    pub example: &'static str,
+    // Whether a value parses as the type the day reads
+    check: fn(&str) -> Result<()>,
+}
+
+impl Parameter {
+    /// `T` is the type the day reads the parameter as.
+    pub const fn new<T>(name: &'static str, input: &'static str, example: &'static str) -> Self
+    where
+        T: FromStr,
+        T::Err: Display
+    {
+        Parameter { name, input, example, check: check::<T> }
+    }
+}
+
+fn check<T>(value: &str) -> Result<()>
+where
+    T: FromStr,
+    T::Err: Display
+{
+    value.parse::<T>().map(drop).map_err(|e| anyhow!("{}", e))
+}
+
+/// Parameter values resolved for a single run of a day.
+#[derive(Debug, Default, Clone)]
+pub struct Params {
+    values: Vec<(&'static str, String)>,
+    aux: Vec<(&'static str, String)>
+}
+
+impl Params {
+    /// Picks the input or example default of every parameter, `overrides`
+    /// win over both. Overrides for parameters the day doesn't declare are
+    /// ignored, values that don't parse as the type of their parameter are
+    /// rejected.
+    pub fn resolve(parameters: &[Parameter], example: bool, overrides: &HashMap<String, String>) -> Result<Self> {
+        let values = parameters.iter().map(|p| {
+            let value = match overrides.get(p.name) {
+                Some(value) => value.clone(),
+                None if example => p.example.to_string(),
+                None => p.input.to_string()
+            };
+            (p.check)(&value).with_context(|| format!("Invalid value {:?} for parameter {}", value, p.name))?;
+            Ok((p.name, value))
+        }).collect::<Result<_>>()?;
+        Ok(Params { values, aux: vec![] })
+    }
+
+    /// Attaches an auxiliary text, like a set of shapes the puzzle ships
+    /// with, under `name`.
+    pub fn with_aux(mut self, name: &'static str, data: String) -> Self {
+        self.aux.push((name, data));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn get<T>(&self, name: &str) -> T
+    where
+        T: FromStr,
+        T::Err: Debug
+    {
+        let (_, value) = self.values.iter()
+            .find(|(n, _)| *n == name)
+            .unwrap_or_else(|| panic!("Parameter {} is not declared by the day", name));
+        value.parse()
+            .unwrap_or_else(|e| panic!("Invalid value {:?} for parameter {}: {:?}", value, name, e))
+    }
+
+    pub fn aux(&self, name: &str) -> &str {
+        self.aux.iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, data)| data.as_str())
+            .unwrap_or_else(|| panic!("Auxiliary data {} is not registered for the day", name))
+    }
+}
+
+impl fmt::Display for Params {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (name, value)) in self.values.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}={}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a `key=value` command line override.
+pub fn parse_override(s: &str) -> Result<(String, String)> {
+    let (key, value) = s.split_once('=')
+        .ok_or_else(|| anyhow!("Expected key=value, got {}", s))?;
+    Ok((key.trim().to_string(), value.trim().to_string()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARAMETERS: &[Parameter] = &[
+        Parameter::new::<i64>("row", "2000000", "10"),
+        Parameter::new::<usize>("rounds", "20", "20"),
+    ];
+
+    #[test]
+    fn test_resolve() {
+        let overrides = HashMap::from([
+            ("rounds".to_string(), "5".to_string()),
+            ("unknown".to_string(), "1".to_string())
+        ]);
+        let input = Params::resolve(PARAMETERS, false, &HashMap::new()).unwrap();
+        assert_eq!(input.get::<i64>("row"), 2_000_000);
+        let example = Params::resolve(PARAMETERS, true, &overrides).unwrap();
+        assert_eq!(example.get::<i64>("row"), 10);
+        assert_eq!(example.get::<usize>("rounds"), 5);
+        assert_eq!(example.to_string(), "row=10, rounds=5");
+    }
+
+    #[test]
+    fn test_resolve_invalid() {
+        let overrides = HashMap::from([("rounds".to_string(), "x".to_string())]);
+        let error = Params::resolve(PARAMETERS, false, &overrides).unwrap_err();
+        assert_eq!(format!("{:#}", error), "Invalid value \"x\" for parameter rounds: invalid digit found in string");
+        let overrides = HashMap::from([("rounds".to_string(), "-1".to_string())]);
+        assert!(Params::resolve(PARAMETERS, false, &overrides).is_err());
+    }
+
+    #[test]
+    fn test_aux() {
+        let params = Params::default().with_aux("shapes", "#".to_string());
+        assert_eq!(params.aux("shapes"), "#");
+    }
+
+    #[test]
+    fn test_parse_override() {
+        assert_eq!(parse_override("row=10").unwrap(), ("row".to_string(), "10".to_string()));
+        assert!(parse_override("row").is_err());
+    }
+}
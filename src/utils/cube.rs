@@ -0,0 +1,305 @@
+use anyhow::{Result, bail};
+use std::collections::VecDeque;
+use super::point::{Coord, Direction, Grid, TOUCHING_DIRECTIONS};
+
+type C = Coord<usize>;
+type Vector = [isize; 3];
+
+fn neg(v: Vector) -> Vector {
+    [-v[0], -v[1], -v[2]]
+}
+
+fn dot(a: Vector, b: Vector) -> isize {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn add(a: Vector, b: Vector) -> Vector {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn scale(v: Vector, s: isize) -> Vector {
+    [v[0] * s, v[1] * s, v[2] * s]
+}
+
+/// A single face of the folded cube.
+///
+/// `right` and `down` are the directions the face's local x and y axes point
+/// to in space once folded, `normal` points out of the cube.
+#[derive(Debug, Clone, Copy)]
+pub struct Face {
+    pub origin: C,
+    normal: Vector,
+    right: Vector,
+    down: Vector,
+}
+
+impl Face {
+    fn direction_vector(&self, direction: &Direction) -> Vector {
+        match direction {
+            Direction::E => self.right,
+            Direction::W => neg(self.right),
+            Direction::S => self.down,
+            Direction::N => neg(self.down),
+            _ => unreachable!("Only touching directions exist on a cube face")
+        }
+    }
+
+    fn vector_direction(&self, vector: Vector) -> Direction {
+        TOUCHING_DIRECTIONS
+            .into_iter()
+            .find(|d| self.direction_vector(d) == vector)
+            .expect("Vector does not lie in the plane of the face")
+    }
+}
+
+/// A 2D map made out of six square faces that fold into a cube.
+///
+/// Any of the 11 cube nets is detected from the map, face adjacency and edge
+/// rotations are derived by folding the net in space. Coordinates are grid
+/// coordinates (`N` decreases y), as used by [`Grid`].
+#[derive(Debug, Clone)]
+pub struct CubeNet {
+    pub size: usize,
+    pub faces: Vec<Face>,
+}
+
+impl CubeNet {
+    /// Detects the cube net in a grid. `is_void` marks the cells that are not
+    /// part of any face.
+    pub fn detect<V>(grid: &Grid<V>, is_void: impl Fn(&V) -> bool) -> Result<Self> {
+        let area = grid.iter_values().filter(|v| !is_void(v)).count();
+        if area == 0 || area % 6 != 0 {
+            bail!("Map area {} can't be split into 6 square faces", area);
+        }
+        let size = (1..).map(|s| (s, s * s * 6)).find(|(_, a)| *a >= area).unwrap();
+        if size.1 != area {
+            bail!("Map area {} is not 6 times a square", area);
+        }
+        let size = size.0;
+
+        let mut origins: Vec<C> = vec![];
+        for ty in 0..grid.height.div_ceil(size) {
+            for tx in 0..grid.width.div_ceil(size) {
+                let origin = Coord::new(tx * size, ty * size);
+                let tile: Vec<bool> = (0..size).flat_map(|y| (0..size).map(move |x| (x, y)))
+                    .map(|(x, y)| {
+                        let c = Coord::new(origin.x + x, origin.y + y);
+                        grid.contains(&c) && !is_void(grid.get_val(&c))
+                    }).collect();
+                if tile.iter().all(|filled| *filled) {
+                    origins.push(origin);
+                } else if tile.iter().any(|filled| *filled) {
+                    bail!("Tile at {} is only partially filled", origin);
+                }
+            }
+        }
+        if origins.len() != 6 {
+            bail!("Expected 6 faces of size {}, found {}", size, origins.len());
+        }
+
+        // Fold the net by walking from the first face over the shared edges.
+        let mut faces: Vec<Option<Face>> = vec![None; 6];
+        faces[0] = Some(Face { origin: origins[0], normal: [0, 0, -1], right: [1, 0, 0], down: [0, 1, 0] });
+        let mut queue = VecDeque::from([0]);
+        while let Some(i) = queue.pop_front() {
+            let face = faces[i].unwrap();
+            for direction in TOUCHING_DIRECTIONS {
+                let o = face.origin;
+                let neighbour_origin = match direction {
+                    Direction::N => o.y.checked_sub(size).map(|y| Coord::new(o.x, y)),
+                    Direction::E => Some(Coord::new(o.x + size, o.y)),
+                    Direction::S => Some(Coord::new(o.x, o.y + size)),
+                    Direction::W => o.x.checked_sub(size).map(|x| Coord::new(x, o.y)),
+                    _ => unreachable!()
+                };
+                let Some(j) = neighbour_origin.and_then(|no| origins.iter().position(|c| *c == no)) else {
+                    continue;
+                };
+                if faces[j].is_some() {
+                    continue;
+                }
+                let (n, r, d) = (face.normal, face.right, face.down);
+                let (normal, right, down) = match direction {
+                    Direction::E => (r, neg(n), d),
+                    Direction::W => (neg(r), n, d),
+                    Direction::S => (d, r, neg(n)),
+                    Direction::N => (neg(d), r, n),
+                    _ => unreachable!()
+                };
+                faces[j] = Some(Face { origin: origins[j], normal, right, down });
+                queue.push_back(j);
+            }
+        }
+
+        let faces: Vec<Face> = faces.into_iter().collect::<Option<_>>()
+            .ok_or_else(|| anyhow::anyhow!("Faces of the net are not connected"))?;
+        for (i, face) in faces.iter().enumerate() {
+            if faces[..i].iter().any(|f| f.normal == face.normal) {
+                bail!("Faces overlap when folded, the map is not a cube net");
+            }
+        }
+        Ok(CubeNet { size, faces })
+    }
+
+    /// Returns the face index and face local coordinate of a map coordinate.
+    pub fn face_at(&self, c: &C) -> Option<(usize, C)> {
+        self.faces.iter().position(|f| {
+            (f.origin.x..f.origin.x + self.size).contains(&c.x) &&
+            (f.origin.y..f.origin.y + self.size).contains(&c.y)
+        }).map(|i| {
+            let o = self.faces[i].origin;
+            (i, Coord::new(c.x - o.x, c.y - o.y))
+        })
+    }
+
+    pub fn to_map(&self, face: usize, local: &C) -> C {
+        let o = self.faces[face].origin;
+        Coord::new(o.x + local.x, o.y + local.y)
+    }
+
+    /// The face you end up on when leaving `face` towards `direction`.
+    pub fn adjacent(&self, face: usize, direction: &Direction) -> usize {
+        let exit = self.faces[face].direction_vector(direction);
+        self.faces.iter().position(|f| f.normal == exit).unwrap()
+    }
+
+    /// Clockwise quarter turns the direction of travel makes when crossing
+    /// the edge of `face` towards `direction`.
+    pub fn rotation(&self, face: usize, direction: &Direction) -> usize {
+        let (_, _, new_direction) = self.cross_edge(face, &Coord::new(0, 0), direction);
+        let index = |d: &Direction| TOUCHING_DIRECTIONS.iter().position(|t| t == d).unwrap();
+        (index(&new_direction) + 4 - index(direction)) % 4
+    }
+
+    /// Maps a position on the edge of `face` to the position on the adjacent
+    /// face, along with the new direction of travel. `local` is projected on
+    /// the edge, so only the coordinate along the edge matters.
+    pub fn cross_edge(&self, face: usize, local: &C, direction: &Direction) -> (usize, C, Direction) {
+        let s = self.size as isize;
+        let from = &self.faces[face];
+        let (x, y) = match direction {
+            Direction::N => (local.x, 0),
+            Direction::E => (self.size - 1, local.y),
+            Direction::S => (local.x, self.size - 1),
+            Direction::W => (0, local.y),
+            _ => unreachable!("Only touching directions exist on a cube face")
+        };
+        // Cell centers in space, scaled by two so they stay integers
+        let position = add(
+            scale(from.normal, s),
+            add(scale(from.right, 2 * x as isize + 1 - s), scale(from.down, 2 * y as isize + 1 - s))
+        );
+        let exit = from.direction_vector(direction);
+        let position = add(position, add(exit, neg(from.normal)));
+
+        let to_index = self.adjacent(face, direction);
+        let to = &self.faces[to_index];
+        let new_x = (dot(position, to.right) + s - 1) / 2;
+        let new_y = (dot(position, to.down) + s - 1) / 2;
+        let new_direction = to.vector_direction(neg(from.normal));
+        (to_index, Coord::new(new_x as usize, new_y as usize), new_direction)
+    }
+
+    /// Takes one step on the map, wrapping around the cube when stepping off
+    /// a face.
+    pub fn step(&self, c: &C, direction: &Direction) -> (C, Direction) {
+        let (face, local) = self.face_at(c).expect("Coordinate is not on the cube");
+        let next = match direction {
+            Direction::N => local.y.checked_sub(1).map(|y| Coord::new(local.x, y)),
+            Direction::E => Some(Coord::new(local.x + 1, local.y)).filter(|n| n.x < self.size),
+            Direction::S => Some(Coord::new(local.x, local.y + 1)).filter(|n| n.y < self.size),
+            Direction::W => local.x.checked_sub(1).map(|x| Coord::new(x, local.y)),
+            _ => unreachable!("Only touching directions exist on a cube face")
+        };
+        match next {
+            Some(n) => (self.to_map(face, &n), *direction),
+            None => {
+                let (new_face, new_local, new_direction) = self.cross_edge(face, &local, direction);
+                (self.to_map(new_face, &new_local), new_direction)
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn net(rows: &[&str], size: usize) -> Grid<bool> {
+        let width = rows.iter().map(|r| r.len()).max().unwrap() * size;
+        let map = rows.iter().flat_map(|row| {
+            (0..size).map(move |_| {
+                (0..width).map(|x| row.as_bytes().get(x / size) == Some(&b'#')).collect()
+            })
+        }).collect();
+        Grid::new(map)
+    }
+
+    const NETS: [&[&str]; 11] = [
+        &["#   ", "####", "#   "],
+        &["#   ", "####", " #  "],
+        &["#   ", "####", "  # "],
+        &["#   ", "####", "   #"],
+        &[" #  ", "####", " #  "],
+        &[" #  ", "####", "  # "],
+        &["##  ", " ###", " #  "],
+        &["##  ", " ###", "  # "],
+        &["##  ", " ###", "   #"],
+        &["##  ", " ## ", "  ##"],
+        &["###  ", "  ###"],
+    ];
+
+    #[test]
+    fn test_detect_all_nets() {
+        for rows in NETS {
+            let grid = net(rows, 3);
+            let cube = CubeNet::detect(&grid, |v| !v).unwrap();
+            assert_eq!(cube.size, 3);
+            assert_eq!(cube.faces.len(), 6);
+        }
+    }
+
+    #[test]
+    fn test_reject_invalid_nets() {
+        let grid = net(&["####", "##  "], 2);
+        assert!(CubeNet::detect(&grid, |v| !v).is_err());
+        let grid = net(&["###", "# #"], 2);
+        assert!(CubeNet::detect(&grid, |v| !v).is_err());
+    }
+
+    #[test]
+    fn test_edges_round_trip() {
+        for rows in NETS {
+            let cube = CubeNet::detect(&net(rows, 4), |v| !v).unwrap();
+            for face in 0..6 {
+                for direction in TOUCHING_DIRECTIONS {
+                    for i in 0..4 {
+                        let local = Coord::new(i, i);
+                        let (to, to_local, to_direction) = cube.cross_edge(face, &local, &direction);
+                        assert_ne!(to, face);
+                        let (back, back_local, back_direction) = cube.cross_edge(to, &to_local, &to_direction.opposite());
+                        assert_eq!(back, face);
+                        assert_eq!(back_direction.opposite(), direction);
+                        match direction {
+                            Direction::N | Direction::S => assert_eq!(back_local.x, local.x),
+                            _ => assert_eq!(back_local.y, local.y),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_example_layout() {
+        let cube = CubeNet::detect(&net(&["  # ", "### ", "  ##"], 4), |v| !v).unwrap();
+        // Leaving the right side of face 1 (the A -> B move from the puzzle)
+        let (c, d) = cube.step(&Coord::new(11, 5), &Direction::E);
+        assert_eq!((c, d), (Coord::new(14, 8), Direction::S));
+        // Leaving the bottom of face 4 (the C -> D move from the puzzle)
+        let (c, d) = cube.step(&Coord::new(10, 11), &Direction::S);
+        assert_eq!((c, d), (Coord::new(1, 7), Direction::N));
+        assert_eq!(cube.rotation(0, &Direction::N), 2);
+    }
+}
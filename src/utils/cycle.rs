@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Cycle of a sequence of states, the state at `start + length` is the same
+/// as the one at `start` and the sequence repeats from there on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize
+}
+
+impl Cycle {
+    /// Maps step `n` onto the equivalent step of the first loop.
+    pub fn reduce(&self, n: u64) -> usize {
+        if n < self.start as u64 {
+            n as usize
+        } else {
+            self.start + ((n - self.start as u64) % self.length as u64) as usize
+        }
+    }
+
+    /// Value after `n` steps of a quantity that grows by the same amount on
+    /// every loop, like the height of a tower. `values[i]` is the value after
+    /// `i` steps and has to cover at least one full loop.
+    pub fn extrapolate<T>(&self, values: &[T], n: u64) -> T
+    where
+        T: num::PrimInt
+    {
+        let end = self.start + self.length;
+        assert!(values.len() > end, "Values have to cover the first loop of the cycle");
+        if n < values.len() as u64 {
+            return values[n as usize];
+        }
+        let loops = (n - self.start as u64) / self.length as u64;
+        let growth = values[end] - values[self.start];
+        values[self.reduce(n)] + growth * T::from(loops).unwrap()
+    }
+}
+
+/// Floyd's tortoise and hare, the iterator gets cloned to walk the sequence
+/// several times. Returns `None` if the iterator ends before a cycle shows up.
+pub fn floyd<I>(iter: I) -> Option<Cycle>
+where
+    I: Iterator + Clone,
+    I::Item: PartialEq
+{
+    let mut tortoise = iter.clone();
+    let mut hare = iter.clone();
+    tortoise.next()?;
+    hare.next()?;
+    let mut hare_value = loop {
+        let t = tortoise.next()?;
+        hare.next()?;
+        let h = hare.next()?;
+        if t == h {
+            break h;
+        }
+    };
+
+    let mut from_start = iter;
+    let mut start_value = from_start.next()?;
+    let mut start = 0;
+    while start_value != hare_value {
+        start_value = from_start.next()?;
+        hare_value = hare.next()?;
+        start += 1;
+    }
+
+    let mut length = 1;
+    while hare.next()? != start_value {
+        length += 1;
+    }
+    Some(Cycle { start, length })
+}
+
+/// Brent's algorithm, needs fewer comparisons than [`floyd`] and walks the
+/// sequence twice. Returns `None` if the iterator ends before a cycle shows up.
+pub fn brent<I>(iter: I) -> Option<Cycle>
+where
+    I: Iterator + Clone,
+    I::Item: PartialEq + Clone
+{
+    let mut it = iter.clone();
+    let mut tortoise = it.next()?;
+    let mut hare = it.next()?;
+    let mut power = 1;
+    let mut length = 1;
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = it.next()?;
+        length += 1;
+    }
+
+    let mut tortoise_it = iter.clone();
+    let mut hare_it = iter.skip(length);
+    let mut tortoise = tortoise_it.next()?;
+    let mut hare = hare_it.next()?;
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = tortoise_it.next()?;
+        hare = hare_it.next()?;
+        start += 1;
+    }
+    Some(Cycle { start, length })
+}
+
+/// Detects a cycle from hashed state keys pushed one step at a time, for
+/// simulations that can't be replayed cheaply. The key has to capture
+/// everything the next steps depend on.
+pub struct CycleDetector<K> {
+    seen: HashMap<K, usize>,
+    steps: usize
+}
+
+impl<K> CycleDetector<K>
+where
+    K: Hash + Eq
+{
+    pub fn new() -> Self {
+        CycleDetector { seen: HashMap::new(), steps: 0 }
+    }
+
+    /// Records the key of the next step, returns the cycle once a key repeats.
+    pub fn push(&mut self, key: K) -> Option<Cycle> {
+        let step = self.steps;
+        self.steps += 1;
+        self.seen.insert(key, step).map(|start| Cycle { start, length: step - start })
+    }
+}
+
+impl<K> Default for CycleDetector<K>
+where
+    K: Hash + Eq
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Finds the first repeating key of the sequence.
+pub fn find_by_key<I>(mut iter: I) -> Option<Cycle>
+where
+    I: Iterator,
+    I::Item: Hash + Eq
+{
+    let mut detector = CycleDetector::new();
+    iter.find_map(|key| detector.push(key))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0 1 2 3 4 5 | 3 4 5 | 3 ...
+    fn sequence() -> impl Iterator<Item = u32> + Clone {
+        std::iter::successors(Some(0), |&x| Some(if x == 5 { 3 } else { x + 1 }))
+    }
+
+    #[test]
+    fn test_detection() {
+        let expected = Some(Cycle { start: 3, length: 3 });
+        assert_eq!(floyd(sequence()), expected);
+        assert_eq!(brent(sequence()), expected);
+        assert_eq!(find_by_key(sequence()), expected);
+        assert_eq!(floyd(0..10), None);
+        assert_eq!(brent(0..10), None);
+        assert_eq!(find_by_key(0..10), None);
+        assert_eq!(brent(std::iter::repeat(7)), Some(Cycle { start: 0, length: 1 }));
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        let cycle = Cycle { start: 2, length: 3 };
+        // Grows by 10 every loop after two steps
+        let values: Vec<u64> = vec![0, 1, 5, 7, 12, 15];
+        assert_eq!(cycle.extrapolate(&values, 4), 12);
+        assert_eq!(cycle.extrapolate(&values, 8), 25);
+        assert_eq!(cycle.extrapolate(&values, 9), 27);
+        assert_eq!(cycle.extrapolate(&values, 3 * 1_000_000 + 2), 5 + 10 * 1_000_000);
+        assert_eq!(cycle.reduce(9), 3);
+    }
+}
@@ -0,0 +1,65 @@
+/// Normalizes puzzle text the way every day expects it: a leading BOM is
+/// stripped, CRLF and CR line endings become LF, whitespace only lines
+/// become empty and trailing whitespace at the end of the text is removed.
+pub fn normalize(text: &str) -> String {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    let lines: Vec<&str> = text.split('\n').map(|l| {
+        if l.trim().is_empty() { "" } else { l }
+    }).collect();
+    lines.join("\n").trim_end().to_string()
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+/// Splits text into sections separated by one or more blank lines. Blank
+/// lines at the start or end don't produce empty sections.
+pub fn sections(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let line_end = |from: usize, s: &str| s[from..].find('\n').map_or(s.len(), |i| from + i + 1);
+        loop {
+            if rest.is_empty() {
+                return None;
+            }
+            let end = line_end(0, rest);
+            if !is_blank(&rest[..end]) {
+                break;
+            }
+            rest = &rest[end..];
+        }
+        let mut end = 0;
+        while end < rest.len() {
+            let next = line_end(end, rest);
+            if is_blank(&rest[end..next]) {
+                break;
+            }
+            end = next;
+        }
+        let section = rest[..end].trim_end_matches(['\r', '\n']);
+        rest = &rest[end..];
+        Some(section)
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("\u{feff}a\r\nb\rc\n  \nd \r\n\r\n"), "a\nb\nc\n\nd");
+        assert_eq!(normalize("  x\n"), "  x");
+    }
+
+    #[test]
+    fn test_sections() {
+        let text = "\n1\n2\n\n3\r\n\r\n \n\n4\n5\n\n";
+        assert_eq!(sections(text).collect::<Vec<_>>(), vec!["1\n2", "3", "4\n5"]);
+        assert_eq!(sections("").count(), 0);
+        assert_eq!(sections("single").collect::<Vec<_>>(), vec!["single"]);
+    }
+}
@@ -0,0 +1,277 @@
+use std::fmt;
+use std::ops::{Bound, RangeBounds, RangeInclusive};
+
+/// Converts any bounded integer range into an inclusive one, `None` if it's
+/// empty.
+fn to_inclusive<T, R>(range: R) -> Option<RangeInclusive<T>>
+where
+    T: num::PrimInt,
+    R: RangeBounds<T>
+{
+    let start = match range.start_bound() {
+        Bound::Included(s) => *s,
+        Bound::Excluded(s) => s.checked_add(&T::one())?,
+        Bound::Unbounded => panic!("Range set can't hold unbounded ranges")
+    };
+    let end = match range.end_bound() {
+        Bound::Included(e) => *e,
+        Bound::Excluded(e) => e.checked_sub(&T::one())?,
+        Bound::Unbounded => panic!("Range set can't hold unbounded ranges")
+    };
+    (start <= end).then_some(start..=end)
+}
+
+/// True if a range ending at `end` neither overlaps nor touches one starting
+/// at `start`.
+fn apart<T: num::PrimInt>(end: T, start: T) -> bool {
+    // `end < start` keeps `end + 1` from overflowing
+    end < start && end + T::one() < start
+}
+
+/// A set of integers stored as sorted, disjoint and non touching inclusive
+/// ranges, so values up to `T::MAX` fit.
+///
+/// Inclusive (`a..=b`) and exclusive (`a..b`) ranges are both accepted
+/// wherever a range is expected.
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct RangeSet<T> {
+    ranges: Vec<RangeInclusive<T>>
+}
+
+impl<T> RangeSet<T>
+where
+    T: num::PrimInt
+{
+    pub fn new() -> Self {
+        RangeSet { ranges: vec![] }
+    }
+
+    pub fn ranges(&self) -> &[RangeInclusive<T>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Number of integers in the set, it must fit in `T`.
+    pub fn len(&self) -> T {
+        self.ranges.iter().fold(T::zero(), |acc, r| acc + (*r.end() - *r.start() + T::one()))
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.ranges.first().map(|r| *r.start())
+    }
+
+    pub fn max(&self) -> Option<T> {
+        self.ranges.last().map(|r| *r.end())
+    }
+
+    pub fn insert<R: RangeBounds<T>>(&mut self, range: R) {
+        let Some(range) = to_inclusive(range) else { return };
+        let (mut start, mut end) = range.into_inner();
+        // Ranges that overlap or touch the new one get merged into it
+        let first = self.ranges.partition_point(|r| apart(*r.end(), start));
+        let last = self.ranges.partition_point(|r| !apart(end, *r.start()));
+        if first < last {
+            start = std::cmp::min(start, *self.ranges[first].start());
+            end = std::cmp::max(end, *self.ranges[last - 1].end());
+        }
+        self.ranges.splice(first..last, [start..=end]);
+    }
+
+    pub fn remove<R: RangeBounds<T>>(&mut self, range: R) {
+        let Some(range) = to_inclusive(range) else { return };
+        let (start, end) = range.into_inner();
+        let first = self.ranges.partition_point(|r| *r.end() < start);
+        let last = self.ranges.partition_point(|r| *r.start() <= end);
+        let mut leftovers = vec![];
+        if first < last {
+            // Both neighbours are strictly inside the removed ranges, no overflow
+            if *self.ranges[first].start() < start {
+                leftovers.push(*self.ranges[first].start()..=start - T::one());
+            }
+            if *self.ranges[last - 1].end() > end {
+                leftovers.push(end + T::one()..=*self.ranges[last - 1].end());
+            }
+        }
+        self.ranges.splice(first..last, leftovers);
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        let i = self.ranges.partition_point(|r| r.end() < value);
+        self.ranges.get(i).is_some_and(|r| r.contains(value))
+    }
+
+    /// True if every value of the range is in the set.
+    pub fn contains_range<R: RangeBounds<T>>(&self, range: R) -> bool {
+        let Some(range) = to_inclusive(range) else { return true };
+        let i = self.ranges.partition_point(|r| r.end() < range.start());
+        self.ranges.get(i).is_some_and(|r| r.start() <= range.start() && range.end() <= r.end())
+    }
+
+    /// True if at least one value of the range is in the set.
+    pub fn overlaps<R: RangeBounds<T>>(&self, range: R) -> bool {
+        let Some(range) = to_inclusive(range) else { return false };
+        let i = self.ranges.partition_point(|r| r.end() < range.start());
+        self.ranges.get(i).is_some_and(|r| r.start() <= range.end())
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        for r in other.ranges.iter() {
+            result.insert(r.clone());
+        }
+        result
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (&self.ranges[i], &other.ranges[j]);
+            let start = std::cmp::max(*a.start(), *b.start());
+            let end = std::cmp::min(*a.end(), *b.end());
+            if start <= end {
+                ranges.push(start..=end);
+            }
+            if a.end() < b.end() { i += 1; } else { j += 1; }
+        }
+        RangeSet { ranges }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        for r in other.ranges.iter() {
+            result.remove(r.clone());
+        }
+        result
+    }
+
+    /// The ranges between consecutive ranges of the set.
+    pub fn gaps(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        // Ranges never touch, so every gap holds at least one value
+        self.ranges.windows(2).map(|w| *w[0].end() + T::one()..=*w[1].start() - T::one())
+    }
+
+    /// The parts of `range` that are not covered by the set.
+    pub fn gaps_within<R: RangeBounds<T>>(&self, range: R) -> Self {
+        let mut gaps = RangeSet::new();
+        gaps.insert(range);
+        gaps.difference(self)
+    }
+}
+
+impl<T, R> FromIterator<R> for RangeSet<T>
+where
+    T: num::PrimInt,
+    R: RangeBounds<T>
+{
+    fn from_iter<I: IntoIterator<Item = R>>(iter: I) -> Self {
+        let mut set = RangeSet::new();
+        for range in iter {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+impl<T> From<std::ops::Range<T>> for RangeSet<T>
+where
+    T: num::PrimInt
+{
+    fn from(range: std::ops::Range<T>) -> Self {
+        std::iter::once(range).collect()
+    }
+}
+
+impl<T> From<RangeInclusive<T>> for RangeSet<T>
+where
+    T: num::PrimInt
+{
+    fn from(range: RangeInclusive<T>) -> Self {
+        std::iter::once(range).collect()
+    }
+}
+
+impl<T> fmt::Debug for RangeSet<T>
+where
+    T: fmt::Debug
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.ranges.iter()).finish()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_merges() {
+        let mut set: RangeSet<i64> = RangeSet::new();
+        set.insert(10..15);
+        set.insert(0..=4);
+        set.insert(20..25);
+        assert_eq!(set.ranges(), &[0..=4, 10..=14, 20..=24]);
+        set.insert(5..10);
+        assert_eq!(set.ranges(), &[0..=14, 20..=24]);
+        set.insert(13..=21);
+        assert_eq!(set.ranges().first(), Some(&(0..=24)));
+        assert_eq!(set.len(), 25);
+    }
+
+    #[test]
+    fn test_remove_and_gaps() {
+        let mut set: RangeSet<i32> = (0..=20).into();
+        set.remove(5..10);
+        set.remove(15..=15);
+        assert_eq!(set.ranges(), &[0..=4, 10..=14, 16..=20]);
+        assert_eq!(set.gaps().collect::<Vec<_>>(), vec![5..=9, 15..=15]);
+        assert_eq!(set.gaps_within(-2..=22).ranges(), &[-2..=-1, 5..=9, 15..=15, 21..=22]);
+        assert_eq!(set.min(), Some(0));
+        assert_eq!(set.max(), Some(20));
+    }
+
+    #[test]
+    fn test_queries() {
+        let set: RangeSet<u32> = [2..=4, 6..=8].into_iter().collect();
+        assert!(set.contains(&2));
+        assert!(!set.contains(&5));
+        assert!(set.contains_range(6..=8));
+        assert!(!set.contains_range(4..=6));
+        assert!(set.overlaps(4..=6));
+        assert!(!set.overlaps(9..12));
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a: RangeSet<i32> = [0..10, 20..30].into_iter().collect();
+        let b: RangeSet<i32> = RangeSet::from(5..25);
+        assert_eq!(a.union(&b), RangeSet::from(0..30));
+        assert_eq!(a.intersection(&b).ranges(), &[5..=9, 20..=24]);
+        assert_eq!(a.difference(&b).ranges(), &[0..=4, 25..=29]);
+        assert_eq!(b.difference(&a), RangeSet::from(10..20));
+    }
+
+    #[test]
+    fn test_type_bounds() {
+        let mut set: RangeSet<u8> = (250..=u8::MAX).into();
+        assert_eq!(set.max(), Some(u8::MAX));
+        assert!(set.contains(&u8::MAX));
+        assert!(set.contains_range(254..=255));
+        set.insert(0..=0);
+        set.insert(1..250);
+        assert_eq!(set.ranges(), &[0..=u8::MAX]);
+        set.remove(u8::MAX..=u8::MAX);
+        set.remove(0..1);
+        assert_eq!(set.ranges(), &[1..=254]);
+        assert_eq!(set.gaps_within(0..=u8::MAX).ranges(), &[0..=0, 255..=255]);
+        // Empty ranges at the ends of the type
+        set.insert(u8::MAX..u8::MAX);
+        set.insert(0..0);
+        assert_eq!(set.len(), 254);
+        assert!(!set.overlaps(0..0));
+    }
+}
@@ -1,5 +1,11 @@
 pub mod tree;
 pub mod point;
+pub mod cube;
+pub mod intervals;
+pub mod input;
+pub mod parse;
+pub mod cycle;
+pub mod ocr;
 
 use std::io;
 
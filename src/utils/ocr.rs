@@ -0,0 +1,71 @@
+use anyhow::{Result, anyhow, bail};
+
+pub const GLYPH_WIDTH: usize = 4;
+pub const GLYPH_HEIGHT: usize = 6;
+// Glyphs are separated by an empty column
+const GLYPH_SPACING: usize = GLYPH_WIDTH + 1;
+
+/// Letters of the 4x6 font drawn by the puzzles, rows joined top to bottom.
+const GLYPHS: [(char, &str); 17] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('I', ".###..#...#...#...#..###"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+/// Reads the letters drawn on a screen of lit (`true`) pixels.
+pub fn decode(screen: &[Vec<bool>]) -> Result<String> {
+    if screen.len() != GLYPH_HEIGHT {
+        bail!("Screen has to be {} rows high, got {}", GLYPH_HEIGHT, screen.len());
+    }
+    let width = screen[0].len();
+    (0..width.div_ceil(GLYPH_SPACING)).map(|i| {
+        let x = i * GLYPH_SPACING;
+        let pattern: String = screen.iter().flat_map(|row| {
+            (x..x + GLYPH_WIDTH).map(|x| if row.get(x).copied().unwrap_or(false) { '#' } else { '.' })
+        }).collect();
+        GLYPHS.iter()
+            .find(|(_, glyph)| *glyph == pattern)
+            .map(|(c, _)| *c)
+            .ok_or_else(|| anyhow!("Unknown glyph at column {}", x))
+    }).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen(rows: &[&str]) -> Vec<Vec<bool>> {
+        rows.iter().map(|r| r.chars().map(|c| c == '#').collect()).collect()
+    }
+
+    #[test]
+    fn test_decode() {
+        let hi = screen(&[
+            "#..#..###",
+            "#..#...#.",
+            "####...#.",
+            "#..#...#.",
+            "#..#...#.",
+            "#..#..###",
+        ]);
+        assert_eq!(decode(&hi).unwrap(), "HI");
+        assert!(decode(&hi[..5]).is_err());
+        let unknown = screen(&["####"; 6]);
+        assert!(decode(&unknown).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::{anyhow, Context, Result};
+use std::str::FromStr;
+
+pub use super::input::sections;
+
+/// Extracts every integer of the text, a `-` right in front of the digits
+/// makes it negative. Integers that don't fit `T` are an error.
+pub fn ints<T>(s: &str) -> Result<Vec<T>>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static
+{
+    let bytes = s.as_bytes();
+    let mut result = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let negative = bytes[i] == b'-' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit);
+        if !negative && !bytes[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        i += 1;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        result.push(value(&s[start..i])?);
+    }
+    Ok(result)
+}
+
+/// Parses a single value, the error mentions the text that was rejected.
+pub fn value<T>(s: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static
+{
+    s.trim().parse().with_context(|| format!("Invalid value {:?}", s))
+}
+
+/// Matches `s` against a pattern where every `{}` captures text, e.g.
+/// `"Valve {} has flow rate={}"`. A capture ends where the literal text
+/// following it first appears, the last one runs up to the literal text
+/// that ends the pattern.
+pub fn template<'a, const N: usize>(pattern: &str, s: &'a str) -> Result<[&'a str; N]> {
+    let mismatch = || anyhow!("{:?} doesn't match {:?}", s, pattern);
+    let literals: Vec<&str> = pattern.split("{}").collect();
+    if literals.len() != N + 1 {
+        return Err(anyhow!("Pattern {:?} doesn't have {} captures", pattern, N));
+    }
+    let mut rest = s.strip_prefix(literals[0]).ok_or_else(mismatch)?;
+    let mut captures = [""; N];
+    for (i, (capture, literal)) in captures.iter_mut().zip(literals[1..].iter()).enumerate() {
+        let end = if i + 1 == N {
+            rest.strip_suffix(literal).ok_or_else(mismatch)?.len()
+        } else {
+            rest.find(literal).ok_or_else(mismatch)?
+        };
+        *capture = &rest[..end];
+        rest = &rest[end + literal.len()..];
+    }
+    if !rest.is_empty() {
+        return Err(mismatch());
+    }
+    Ok(captures)
+}
+
+/// Parses every line with `f`, errors are reported with the line number and
+/// the offending line.
+pub fn lines<'a, T, F>(text: &'a str, mut f: F) -> Result<Vec<T>>
+where
+    F: FnMut(&'a str) -> Result<T>
+{
+    text.lines().enumerate().map(|(i, line)| {
+        f(line).with_context(|| format!("Line {}: {:?}", i + 1, line))
+    }).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ints() {
+        let line = "Sensor at x=2, y=-18: closest beacon is at x=-2, y=15";
+        assert_eq!(ints::<i64>(line).unwrap(), vec![2, -18, -2, 15]);
+        assert_eq!(ints::<u32>("move 13 from 8 to 9").unwrap(), vec![13, 8, 9]);
+        assert!(ints::<i32>("no numbers - here").unwrap().is_empty());
+        assert_eq!(ints::<u8>("1, 300").unwrap_err().to_string(), "Invalid value \"300\"");
+        assert!(ints::<u32>("x=-2").is_err());
+    }
+
+    #[test]
+    fn test_template() {
+        let line = "Valve BB has flow rate=13; tunnels lead to valves CC, AA";
+        let [name, rate, tunnels] = template("Valve {} has flow rate={}; {}", line).unwrap();
+        assert_eq!((name, rate, tunnels), ("BB", "13", "tunnels lead to valves CC, AA"));
+        assert!(template::<1>("Valve {} has", "Valve BB had").is_err());
+        assert!(template::<2>("Valve {}", "Valve BB").is_err());
+        assert_eq!(template("Blueprint {}: {}.", "Blueprint 1: a. b.").unwrap(), ["1", "a. b"]);
+    }
+
+    #[test]
+    fn test_lines_error() {
+        let error = lines("1\n2\nx\n4", value::<u32>).unwrap_err();
+        assert!(format!("{:#}", error).starts_with("Line 3: \"x\""));
+        assert_eq!(lines("1\n2", value::<u32>).unwrap(), vec![1, 2]);
+    }
+}
@@ -1,5 +1,6 @@
 use std::fmt::{self, Display};
 use std::cmp::Ordering;
+use std::str::FromStr;
 use std::ops::{Add, Sub, AddAssign, SubAssign};
 use anyhow::{Error, anyhow};
 use funty::Signed;
@@ -146,26 +147,44 @@ impl<T> From<(T, T)> for Coord<T>
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Direction { 
     N, E, S, W,
     NE, NW, SE, SW
 }
 
+impl FromStr for Direction {
+    type Err = Error;
+
+    /// Parses compass names (`N`, `NE`, ...), `U`/`D`/`L`/`R` letters,
+    /// arrows (`↑`, `↗`, ...) and `^`/`v`/`<`/`>`. Letters are case
+    /// insensitive, like for [`HexDirection`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "N" | "U" | "^" | "↑" => Ok(Direction::N),
+            "NE" | "↗" => Ok(Direction::NE),
+            "E" | "R" | ">" | "→" => Ok(Direction::E),
+            "SE" | "↘" => Ok(Direction::SE),
+            "S" | "D" | "V" | "↓" => Ok(Direction::S),
+            "SW" | "↙" => Ok(Direction::SW),
+            "W" | "L" | "<" | "←" => Ok(Direction::W),
+            "NW" | "↖" => Ok(Direction::NW),
+            _ => Err(anyhow!["Invalid value for direction `{}`", s])
+        }
+    }
+}
+
 impl TryFrom<&str> for Direction {
     type Error = Error;
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        match value {
-            "N" => Ok(Direction::N),
-            "NE" => Ok(Direction::NE),
-            "E" => Ok(Direction::E),
-            "SE" => Ok(Direction::SE),
-            "S" => Ok(Direction::S),
-            "SW" => Ok(Direction::SW),
-            "W" => Ok(Direction::W),
-            "NW" => Ok(Direction::NW),
-            _ => Err(anyhow!["Invalid value for direction `{}`", value])
-        }
+        value.parse()
+    }
+}
+
+impl TryFrom<char> for Direction {
+    type Error = Error;
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        value.encode_utf8(&mut [0; 4]).parse()
     }
 }
 
@@ -203,15 +222,120 @@ impl Direction {
         }
     }
 
-    pub fn rotate(&self, degrees: isize) -> Self {
-        if degrees % 45 != 0 {
-            panic!("Invalid value for rotation degrees ({}). Degrees need to be in 45 steps (divisible by 45)", degrees);
-        }
-        let rotations: isize = degrees / 45;
+    /// Rotates clockwise by `steps` eighths of a full turn (45 degrees each),
+    /// negative steps rotate counter-clockwise.
+    pub fn rotate_steps(&self, steps: isize) -> Self {
         let index = DIRECTIONS.iter().position(|d| d == self).unwrap() as isize;
-        let new_index = (index + rotations).rem_euclid(DIRECTIONS.len() as isize) as usize;
+        let new_index = (index + steps).rem_euclid(DIRECTIONS.len() as isize) as usize;
         DIRECTIONS[new_index]
     }
+
+    /// Rotates clockwise by `degrees`, returns `None` if the degrees are not
+    /// a multiple of 45.
+    pub fn checked_rotate(&self, degrees: isize) -> Option<Self> {
+        if degrees % 45 != 0 {
+            return None;
+        }
+        Some(self.rotate_steps(degrees / 45))
+    }
+
+    pub fn turn_left(&self) -> Self {
+        self.rotate_steps(-2)
+    }
+
+    pub fn turn_right(&self) -> Self {
+        self.rotate_steps(2)
+    }
+
+    pub fn opposite(&self) -> Self {
+        self.rotate_steps(4)
+    }
+
+    /// Unit step in this direction, with `N` pointing towards positive y.
+    pub fn delta<T>(&self) -> Coord<T>
+    where 
+        T: num::Signed
+    {
+        self.into()
+    }
+}
+
+/// Directions between the cells of a flat topped hexagonal grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HexDirection {
+    N, NE, SE, S, SW, NW
+}
+
+pub const HEX_DIRECTIONS: [HexDirection; 6] = [
+    HexDirection::N, HexDirection::NE, HexDirection::SE,
+    HexDirection::S, HexDirection::SW, HexDirection::NW
+];
+
+impl HexDirection {
+    /// Rotates clockwise by `steps` sixths of a full turn.
+    pub fn rotate_steps(&self, steps: isize) -> Self {
+        let index = HEX_DIRECTIONS.iter().position(|d| d == self).unwrap() as isize;
+        let new_index = (index + steps).rem_euclid(HEX_DIRECTIONS.len() as isize) as usize;
+        HEX_DIRECTIONS[new_index]
+    }
+
+    pub fn turn_left(&self) -> Self {
+        self.rotate_steps(-1)
+    }
+
+    pub fn turn_right(&self) -> Self {
+        self.rotate_steps(1)
+    }
+
+    pub fn opposite(&self) -> Self {
+        self.rotate_steps(3)
+    }
+
+    /// Unit step in axial coordinates, `x` is the column (q) and `y` the
+    /// row (r) which grows towards `S`.
+    pub fn delta<T>(&self) -> Coord<T>
+    where 
+        T: num::Signed
+    {
+        match self {
+            HexDirection::N  => Coord::new( T::zero(), -T::one()),
+            HexDirection::NE => Coord::new( T::one(),  -T::one()),
+            HexDirection::SE => Coord::new( T::one(),   T::zero()),
+            HexDirection::S  => Coord::new( T::zero(),  T::one()),
+            HexDirection::SW => Coord::new(-T::one(),   T::one()),
+            HexDirection::NW => Coord::new(-T::one(),   T::zero()),
+        }
+    }
+}
+
+impl FromStr for HexDirection {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "N" => Ok(HexDirection::N),
+            "NE" => Ok(HexDirection::NE),
+            "SE" => Ok(HexDirection::SE),
+            "S" => Ok(HexDirection::S),
+            "SW" => Ok(HexDirection::SW),
+            "NW" => Ok(HexDirection::NW),
+            _ => Err(anyhow!["Invalid value for hex direction `{}`", s])
+        }
+    }
+}
+
+impl fmt::Display for HexDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            HexDirection::N => "N",
+            HexDirection::NE => "NE",
+            HexDirection::SE => "SE",
+            HexDirection::S => "S",
+            HexDirection::SW => "SW",
+            HexDirection::NW => "NW"
+        };
+        write!(f, "{}", s)
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -288,16 +412,7 @@ impl<V> Grid<V>
     }
 
     pub fn get_neighbour(&self, coord: &Coord<usize>, direction: &Direction) -> Option<Coord<usize>> {
-        match direction {
-            Direction::N => if coord.y == 0 { None } else { Some(Coord::new(coord.x, coord.y - 1)) },            
-            Direction::E => if coord.x == self.width - 1 { None } else { Some(Coord::new(coord.x + 1, coord.y)) },
-            Direction::S => if coord.y == self.height - 1 { None } else { Some(Coord::new(coord.x, coord.y + 1)) },
-            Direction::W => if coord.x == 0 { None } else { Some(Coord::new(coord.x - 1, coord.y)) },
-            Direction::NE => if coord.x == self.width - 1 || coord.y == 0 { None } else { Some(Coord::new(coord.x + 1, coord.y - 1))},
-            Direction::SE => if coord.x == self.width - 1 || coord.y == self.height - 1 { None } else { Some(Coord::new(coord.x + 1, coord.y + 1))},
-            Direction::SW => if coord.x == 0 || coord.y == self.height - 1 { None } else { Some(Coord::new(coord.x - 1, coord.y + 1))},
-            Direction::NW => if coord.x == 0 || coord.y == 0 { None } else { Some(Coord::new(coord.x - 1, coord.y - 1))},
-        }
+        neighbour_within(self.height, self.width, coord, direction)
     }
 
     pub fn get_index(&self, coord: &Coord<usize>) -> usize {
@@ -399,21 +514,25 @@ impl<V> Grid<V>
             })
     }
 
+    /// Casts a ray from `current_coord` (excluded) in any of the eight
+    /// directions, ending at the edge of the grid.
     pub fn direction_iter(&self, direction: Direction, current_coord: Coord<usize>) -> GridDirectionIterator {
-        let axis = direction.affected_axes()[0];
         GridDirectionIterator {
             height: self.height,
             width: self.width,
-            direction, current_coord, axis
+            direction, current_coord,
+            steps_left: None
         }
     }
 
+    /// Same as [`Grid::direction_iter`], but wraps around to the opposite edge
+    /// of the grid instead of ending.
     pub fn wrapped_direction_iter(&self, direction: Direction, current_coord: Coord<usize>) -> GridWrappedDirectionIterator {
-        let axis = direction.affected_axes()[0];
         GridWrappedDirectionIterator { 
             height: self.height, 
             width: self.width, 
-            direction, current_coord, axis 
+            direction, current_coord,
+            steps_left: None
         }
     }
 }
@@ -482,72 +601,135 @@ where
 }
 
 
-// Endles iterator of the grid in specified direction
-// When it gets to the edge it jumps to the other side and 
-// continues iterating in that direction.
+/// Neighbour of `coord` in `direction`, `None` if it leaves a grid of the
+/// given size.
+pub fn neighbour_within(height: usize, width: usize, coord: &Coord<usize>, direction: &Direction) -> Option<Coord<usize>> {
+    let x = match direction {
+        Direction::E | Direction::NE | Direction::SE => Some(coord.x + 1).filter(|x| *x < width),
+        Direction::W | Direction::NW | Direction::SW => coord.x.checked_sub(1),
+        Direction::N | Direction::S => Some(coord.x)
+    };
+    let y = match direction {
+        Direction::S | Direction::SE | Direction::SW => Some(coord.y + 1).filter(|y| *y < height),
+        Direction::N | Direction::NE | Direction::NW => coord.y.checked_sub(1),
+        Direction::E | Direction::W => Some(coord.y)
+    };
+    Some(Coord::new(x?, y?))
+}
 
+/// Neighbour of `coord` in `direction`, wrapping around the edges of a grid
+/// of the given size.
+pub fn neighbour_wrapped(height: usize, width: usize, coord: &Coord<usize>, direction: &Direction) -> Coord<usize> {
+    let x = match direction {
+        Direction::E | Direction::NE | Direction::SE => (coord.x + 1) % width,
+        Direction::W | Direction::NW | Direction::SW => (coord.x + width - 1) % width,
+        Direction::N | Direction::S => coord.x
+    };
+    let y = match direction {
+        Direction::S | Direction::SE | Direction::SW => (coord.y + 1) % height,
+        Direction::N | Direction::NE | Direction::NW => (coord.y + height - 1) % height,
+        Direction::E | Direction::W => coord.y
+    };
+    Coord::new(x, y)
+}
 
+/// Iterator of the grid coordinates in specified direction, stops at the
+/// edge of the grid.
 pub struct GridDirectionIterator {
     height: usize,
     width: usize,
     direction: Direction,
     current_coord: Coord<usize>,
-    axis: Axis,
+    steps_left: Option<usize>,
+}
+
+impl GridDirectionIterator {
+    /// Limits the ray to at most `steps` coordinates.
+    pub fn steps(mut self, steps: usize) -> Self {
+        self.steps_left = Some(steps);
+        self
+    }
+
+    /// Stops the ray at (and including) the first coordinate matching the
+    /// predicate.
+    pub fn stop_at<P>(self, predicate: P) -> StopAt<Self, P>
+    where P: FnMut(&Coord<usize>) -> bool
+    {
+        StopAt { iter: self, predicate, done: false }
+    }
 }
 
 impl Iterator for GridDirectionIterator {
     type Item = Coord<usize>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let val = self.current_coord.get_mut(&self.axis);
-        match self.direction {
-            Direction::S => {
-                if *val == self.height - 1 { return None; } else { *val += 1; }
-            }
-            Direction::E => {
-                if *val == self.width - 1 { return None; } else { *val += 1; }
-            },
-            Direction::N => {
-                if *val == 0 { return None; } else { *val -= 1; }
-            }
-            Direction::W => {
-                if *val == 0 { return None; } else { *val -= 1; }
-            },
-            _ => unimplemented!("Iterator for direction {:?} is not implemented.", self.direction)
-        }        
-        Some(self.current_coord.clone())
+        if let Some(steps_left) = self.steps_left.as_mut() {
+            *steps_left = steps_left.checked_sub(1)?;
+        }
+        self.current_coord = neighbour_within(self.height, self.width, &self.current_coord, &self.direction)?;
+        Some(self.current_coord)
     }
 }
 
+// Endles iterator of the grid in specified direction
+// When it gets to the edge it jumps to the other side and 
+// continues iterating in that direction.
 pub struct GridWrappedDirectionIterator {
     height: usize,
     width: usize,
     direction: Direction,
     current_coord: Coord<usize>,
-    axis: Axis,
+    steps_left: Option<usize>,
+}
+
+impl GridWrappedDirectionIterator {
+    /// Limits the ray to at most `steps` coordinates.
+    pub fn steps(mut self, steps: usize) -> Self {
+        self.steps_left = Some(steps);
+        self
+    }
+
+    /// Stops the ray at (and including) the first coordinate matching the
+    /// predicate.
+    pub fn stop_at<P>(self, predicate: P) -> StopAt<Self, P>
+    where P: FnMut(&Coord<usize>) -> bool
+    {
+        StopAt { iter: self, predicate, done: false }
+    }
 }
 
 impl Iterator for GridWrappedDirectionIterator {
     type Item = Coord<usize>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let val = self.current_coord.get_mut(&self.axis);
-        match self.direction {
-            Direction::S => {
-                if *val == self.height - 1 { *val = 0; } else { *val += 1; }
-            }
-            Direction::E => {
-                if *val == self.width - 1 { *val = 0; } else { *val += 1; }
-            },
-            Direction::N => {
-                if *val == 0 { *val = self.height - 1; } else { *val -= 1; }
-            }
-            Direction::W => {
-                if *val == 0 { *val = self.width - 1; } else { *val -= 1; }
-            },
-            _ => unimplemented!("Iterator for direction {:?} is not implemented.", self.direction)
-        }        
-        Some(self.current_coord.clone())
+        if let Some(steps_left) = self.steps_left.as_mut() {
+            *steps_left = steps_left.checked_sub(1)?;
+        }
+        self.current_coord = neighbour_wrapped(self.height, self.width, &self.current_coord, &self.direction);
+        Some(self.current_coord)
+    }
+}
+
+pub struct StopAt<I, P> {
+    iter: I,
+    predicate: P,
+    done: bool
+}
+
+impl<I, P> Iterator for StopAt<I, P>
+where
+    I: Iterator<Item = Coord<usize>>,
+    P: FnMut(&Coord<usize>) -> bool
+{
+    type Item = Coord<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let c = self.iter.next()?;
+        self.done = (self.predicate)(&c);
+        Some(c)
     }
 }
 
@@ -569,19 +751,57 @@ mod tests {
     fn test_direction_rotation() {
         use Direction::*;
 
-        assert_eq!(N.rotate(90), E);
-        assert_eq!(N.rotate(-90), W);
-        assert_eq!(N.rotate(180), S);
-        assert_eq!(N.rotate(-180), S);
-        assert_eq!(N.rotate(-45), NW);
-        assert_eq!(N.rotate(45), NE);
-        assert_eq!(N.rotate(135), SE);
-        assert_eq!(N.rotate(-135), SW);
-        assert_eq!(N.rotate(360), N);
+        assert_eq!(N.checked_rotate(90), Some(E));
+        assert_eq!(N.checked_rotate(-90), Some(W));
+        assert_eq!(N.checked_rotate(180), Some(S));
+        assert_eq!(N.checked_rotate(-180), Some(S));
+        assert_eq!(N.checked_rotate(-45), Some(NW));
+        assert_eq!(N.checked_rotate(45), Some(NE));
+        assert_eq!(N.checked_rotate(135), Some(SE));
+        assert_eq!(N.checked_rotate(-135), Some(SW));
+        assert_eq!(N.checked_rotate(360), Some(N));
+        assert_eq!(N.checked_rotate(30), None);
 
-        assert_eq!(S.rotate(90), W);
-        assert_eq!(S.rotate(-90), E);
+        assert_eq!(S.checked_rotate(90), Some(W));
+        assert_eq!(S.checked_rotate(-90), Some(E));
 
+        assert_eq!(N.turn_right(), E);
+        assert_eq!(W.turn_right(), N);
+        assert_eq!(N.turn_left(), W);
+        assert_eq!(NE.opposite(), SW);
+        assert_eq!(E.delta::<i32>(), Coord::new(1, 0));
+    }
+
+    #[test]
+    fn test_direction_parsing() {
+        use Direction::*;
+
+        let parsed: Vec<Direction> = ["U", "R", "D", "L", "^", ">", "v", "<", "↑", "↘", "NW"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        assert_eq!(parsed, vec![N, E, S, W, N, E, S, W, N, SE, NW]);
+        assert_eq!(Direction::try_from('<').unwrap(), W);
+        assert!("X".parse::<Direction>().is_err());
+        let parsed: Vec<Direction> = ["u", "r", "ne", "sW", "V"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        assert_eq!(parsed, vec![N, E, NE, SW, S]);
+    }
+
+    #[test]
+    fn test_hex_direction() {
+        use HexDirection::*;
+
+        assert_eq!(N.turn_right(), NE);
+        assert_eq!(N.turn_left(), NW);
+        assert_eq!(SE.opposite(), NW);
+        let sum = HEX_DIRECTIONS.iter().fold(Coord::new(0, 0), |acc, d| acc + d.delta::<i32>());
+        assert_eq!(sum, Coord::new(0, 0));
+        assert_eq!("sw".parse::<HexDirection>().unwrap(), SW);
+        assert_eq!("nE".parse::<HexDirection>().unwrap(), NE);
+        assert!("x".parse::<HexDirection>().is_err());
     }
 
     #[test]
@@ -602,6 +822,22 @@ mod tests {
         ])
     }
 
+    #[test]
+    fn test_direction_iter() {
+        let grid = construct_grid();
+        let ray: Vec<_> = grid.direction_iter(Direction::SE, Coord::new(6, 5)).collect();
+        assert_eq!(ray, vec![Coord::new(7, 6), Coord::new(8, 7), Coord::new(9, 8)]);
+        let ray: Vec<_> = grid.direction_iter(Direction::NW, Coord::new(1, 5)).collect();
+        assert_eq!(ray, vec![Coord::new(0, 4)]);
+        let ray: Vec<_> = grid.direction_iter(Direction::W, Coord::new(5, 0)).steps(2).collect();
+        assert_eq!(ray, vec![Coord::new(4, 0), Coord::new(3, 0)]);
+        let ray: Vec<_> = grid.direction_iter(Direction::S, Coord::new(0, 0)).stop_at(|c| c.y == 2).collect();
+        assert_eq!(ray, vec![Coord::new(0, 1), Coord::new(0, 2)]);
+
+        let ray: Vec<_> = grid.wrapped_direction_iter(Direction::NE, Coord::new(9, 1)).steps(3).collect();
+        assert_eq!(ray, vec![Coord::new(0, 0), Coord::new(1, 9), Coord::new(2, 8)]);
+    }
+
     #[test]
     fn test_contains() {
         let grid = construct_grid();
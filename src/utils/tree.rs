@@ -1,470 +1,290 @@
-use core::fmt::Debug;
 use std::collections::VecDeque;
-use std::fmt::{self};
-use std::ops::Deref;
-use std::sync::{Arc, RwLock, Weak};
-
-type NodeDataRef<T> = Arc<NodeData<T>>;
-type WeakNodeRef<T> = Weak<NodeData<T>>;
-type Parent<T> = RwLock<WeakNodeRef<T>>;
-type Child<T> = NodeDataRef<T>;
-type Children<T> = RwLock<Vec<Child<T>>>;
-
-pub struct NodeData<T>
-where 
-    T: Debug,
-{
-    pub value: RwLock<T>,
-    pub parent: Parent<T>,
-    pub children: Children<T>
-}
+use std::fmt::{self, Debug};
 
-impl<T> Debug for NodeData<T>
-where
-    T: Debug,
-{
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut parent_msg = String::new();
-        if let Some(parent) = self.parent.read().unwrap().upgrade() {
-            parent_msg.push_str(format!("📦 {:?}", parent.value.read().unwrap()).as_str());            
-        } else {
-            parent_msg.push_str("🚫 None");
-        }
-        f.debug_struct("Node")
-            .field("value", &self.value)
-            .field("parent", &parent_msg)
-            .field("children", &self.children)
-            .finish()
+/// Handle of a node inside of a [`Tree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    pub fn index(&self) -> usize {
+        self.0
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Node<T: Debug> {
-    pub arc_ref: NodeDataRef<T>,
+pub struct NodeData<T> {
+    pub value: T,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>
 }
 
-impl<T> Deref for Node<T>
-where 
-    T: Debug
-{
-    type Target = NodeData<T>;
+/// Arena backed tree. Nodes are never removed, so a [`NodeId`] stays valid
+/// for the whole life of the tree.
+pub struct Tree<T> {
+    nodes: Vec<NodeData<T>>
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.arc_ref
+impl<T> Tree<T> {
+    pub fn new(root_value: T) -> Self {
+        Tree {
+            nodes: vec![NodeData { value: root_value, parent: None, children: vec![] }]
+        }
     }
-}
 
-impl<T> Node<T>
-where
-    T: Debug,
-{
-    pub fn new(value: T) -> Node<T> {
-        let new_node = NodeData {
-            value: RwLock::new(value),
-            parent: RwLock::new(Weak::new()),
-            children: RwLock::new(Vec::new())
-        };
-
-        let arc_ref = Arc::new(new_node);
-        Node { arc_ref }
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
     }
 
-    pub fn get_copy_of_internal_arc(self: &Self) -> NodeDataRef<T> {
-        Arc::clone(&self.arc_ref)
+    pub fn len(&self) -> usize {
+        self.nodes.len()
     }
 
-    pub fn create_and_add_child(&self, value: T) -> NodeDataRef<T> {
-        let new_child = Node::new(value);
-        self.add_child_and_update_its_parent(&new_child);
-        new_child.get_copy_of_internal_arc()
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
     }
 
-    pub fn add_child_and_update_its_parent(&self, child: &Node<T>) {
-        {
-            let mut my_children = self.arc_ref.children.write().unwrap();
-            my_children.push(child.get_copy_of_internal_arc());
-        }
+    pub fn add_child(&mut self, parent: NodeId, value: T) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(NodeData { value, parent: Some(parent), children: vec![] });
+        self.nodes[parent.0].children.push(id);
+        id
+    }
 
-        {
-            let mut childs_parent = child.arc_ref.parent.write().unwrap();
-            *childs_parent = Arc::downgrade(&self.get_copy_of_internal_arc());
-        }
+    pub fn get(&self, id: NodeId) -> &T {
+        &self.nodes[id.0].value
     }
 
-    pub fn get_parent(&self) -> Option<NodeDataRef<T>> {
-        let my_parent_weak = self.arc_ref.parent.read().unwrap();
-        if let Some(my_parent_arc_ref) = my_parent_weak.upgrade() {
-            Some(my_parent_arc_ref)
-        } else {
-            None
-        }
-    }    
+    pub fn get_mut(&mut self, id: NodeId) -> &mut T {
+        &mut self.nodes[id.0].value
+    }
 
-    pub fn inorder_iter(&self) -> InOrderTraversal<T> {
-        let node = Node { arc_ref: self.get_copy_of_internal_arc() };
-        InOrderTraversal::new(node)
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].parent
     }
 
-    pub fn inrevorder_iter(&self) -> InRevOrderTraversal<T> {
-        let node = Node { arc_ref: self.get_copy_of_internal_arc() };
-        InRevOrderTraversal::new(node)
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.nodes[id.0].children
     }
 
-    pub fn inlevel_iter(&self) -> InLevelTraversal<T> {
-        let node = Node { arc_ref: self.get_copy_of_internal_arc() };
-        InLevelTraversal::new(node)
+    /// Depth first traversal where a node comes before its children,
+    /// yields the level relative to `id` along with the node.
+    pub fn inorder_iter(&self, id: NodeId) -> InOrderTraversal<'_, T> {
+        InOrderTraversal { tree: self, stack: vec![(0, id)] }
     }
-}
 
+    /// Depth first traversal where a node comes after all of its children.
+    pub fn inrevorder_iter(&self, id: NodeId) -> InRevOrderTraversal<'_, T> {
+        InRevOrderTraversal { tree: self, stack: vec![(id, 0)] }
+    }
 
-pub struct InRevOrderTraversal<T>
-where
-    T: Debug
-{
-    current: Node<T>,
-    queue: Vec<usize> // Number of children we have checked for each node down the tree
-}
+    /// Breadth first traversal, yields the level relative to `id` along
+    /// with the node.
+    pub fn inlevel_iter(&self, id: NodeId) -> InLevelTraversal<'_, T> {
+        InLevelTraversal { tree: self, queue: VecDeque::from([(0, id)]) }
+    }
 
-impl<T> InRevOrderTraversal<T>
-where
-    T: Debug
-{
-    fn new(node: Node<T>) -> Self {
-        InRevOrderTraversal { current: node, queue: vec![0] }
+    /// Aggregates the subtree of `id` bottom up. `f` receives each node with
+    /// the aggregates of its children, the result holds the aggregate of
+    /// every node in the subtree.
+    pub fn fold_postorder<A, F>(&self, id: NodeId, mut f: F) -> Vec<(NodeId, A)>
+    where
+        A: Clone,
+        F: FnMut(NodeId, &T, &[A]) -> A
+    {
+        let mut aggregates: Vec<Option<A>> = vec![None; self.nodes.len()];
+        let mut result = vec![];
+        for node in self.inrevorder_iter(id) {
+            let children: Vec<A> = self.children(node)
+                .iter()
+                .map(|c| aggregates[c.0].clone().unwrap())
+                .collect();
+            let aggregate = f(node, self.get(node), &children);
+            aggregates[node.0] = Some(aggregate.clone());
+            result.push((node, aggregate));
+        }
+        result
     }
 }
 
-impl<T> Iterator for InRevOrderTraversal<T>
+impl<T> Debug for Tree<T>
 where
     T: Debug
 {
-    type Item = NodeDataRef<T>;
-    
-    fn next(&mut self) -> Option<Self::Item> {
-        let num_children = self.current.children.read().unwrap().len();
-        match (self.current.get_parent(), self.queue.pop()) {
-            (_, None) => None,
-            (None, Some(i)) if i >= num_children => {
-                Some(self.current.get_copy_of_internal_arc())
-            },
-            (Some(parent), Some(i)) if i >= num_children => {
-                let current = self.current.get_copy_of_internal_arc();
-                self.current = Node { arc_ref: parent };
-                Some(current)
-            },
-            (_, Some(i)) => {                
-                let next_child = Arc::clone(&self.current.children.read().unwrap()[i]);
-                self.current = Node { arc_ref: next_child};
-                self.queue.push(i + 1);
-                self.queue.push(0);
-                self.next()
-            }
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (level, id) in self.inorder_iter(self.root()) {
+            writeln!(f, "{:indent$}{:?}", "", self.get(id), indent = level * 2)?;
         }
+        Ok(())
     }
 }
 
 
-pub struct InOrderTraversal<T>
-where
-    T: Debug
-{
-    current: Option<Node<T>>,
-    queue: VecDeque<Node<T>>,
-    children_count: VecDeque<usize>,
+pub struct InOrderTraversal<'a, T> {
+    tree: &'a Tree<T>,
+    stack: Vec<(usize, NodeId)>
 }
 
-impl<T> InOrderTraversal<T>
-where
-    T: Debug
-{
-    fn new(node: Node<T>) -> Self {
-        InOrderTraversal { 
-            current: Some(node), 
-            queue: VecDeque::new(),
-            children_count: VecDeque::new(),
-        }
-    }
-}
+impl<'a, T> Iterator for InOrderTraversal<'a, T> {
+    type Item = (usize, NodeId);
 
-impl<T> Iterator for InOrderTraversal<T>
-where
-    T: Debug
-{
-    type Item = (usize, NodeDataRef<T>);
-    
     fn next(&mut self) -> Option<Self::Item> {
-        match (self.current.take(), &mut self.queue) {
-            (None, q) if q.is_empty() => None,
-            (None, q) => {
-                self.current = q.pop_back();                
-                self.next()
-            },
-            (Some(node), q) => {
-                let children = node.children.read().unwrap();            
-                'children: loop {
-                    if let Some(current_children_count) = self.children_count.pop_back() {
-                        if current_children_count != 0 {                          
-                            self.children_count.push_back(current_children_count - 1);
-                            break 'children;
-                        } else {
-                            // Continue clearing 0's from children count
-                            continue 'children;
-                        }
-                    } else {
-                        break 'children;
-                    }
-                }
-
-                let current_level = self.children_count.len();
-                self.children_count.push_back(children.len());                                    
-                   
-                for child in children.iter().rev() {
-                    q.push_back( Node { arc_ref: Arc::clone(child) });                    
-                }
-                Some((current_level, node.get_copy_of_internal_arc()))
-            }
+        let (level, id) = self.stack.pop()?;
+        for child in self.tree.children(id).iter().rev() {
+            self.stack.push((level + 1, *child));
         }
+        Some((level, id))
     }
 }
 
 
-pub struct InLevelTraversal<T> 
-where
-    T: Debug
-{
-    current: Option<Node<T>>,
-    current_level: usize,
-    current_level_count: usize,
-    next_level_count: usize,
-    queue: VecDeque<Node<T>>
+pub struct InRevOrderTraversal<'a, T> {
+    tree: &'a Tree<T>,
+    stack: Vec<(NodeId, usize)> // Node and number of its children already visited
 }
 
+impl<'a, T> Iterator for InRevOrderTraversal<'a, T> {
+    type Item = NodeId;
 
-impl<T> InLevelTraversal<T>
-where
-    T: Debug 
-{
-    fn new(node: Node<T>) -> Self {  
-        InLevelTraversal { 
-            current: Some(node),             
-            current_level: 0, 
-            current_level_count: 1,
-            next_level_count: 0,
-            queue: VecDeque::new()
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (id, visited) = self.stack.last_mut()?;
+            let id = *id;
+            match self.tree.children(id).get(*visited) {
+                Some(child) => {
+                    *visited += 1;
+                    self.stack.push((*child, 0));
+                },
+                None => {
+                    self.stack.pop();
+                    return Some(id);
+                }
+            }
         }
     }
 }
 
-impl <T> Iterator for InLevelTraversal<T>
-where
-    T: Debug
-{
-    type Item = (usize, NodeDataRef<T>);
-
-    fn next(&mut self) -> Option<Self::Item> {        
-        match (self.current.take(), &mut self.queue) {            
-            (None, q) if q.is_empty() => { None },
-            (None, q) => {                
-                self.current = q.pop_front();
-                self.next()
-            },
-            (Some(node), q) => {
-                if self.current_level_count == 0 {
-                    self.current_level += 1;
-                    self.current_level_count = self.next_level_count;
-                    self.next_level_count = 0;
-                }
-                self.current_level_count -= 1;
 
-                let children = node.children.read().unwrap();                                                                         
-                for child in children.iter() {
-                    q.push_back( Node { arc_ref: Arc::clone(child) });
-                }                
-                self.next_level_count += children.len();
+pub struct InLevelTraversal<'a, T> {
+    tree: &'a Tree<T>,
+    queue: VecDeque<(usize, NodeId)>
+}
 
-                Some((self.current_level, node.get_copy_of_internal_arc()))
-            }
+impl<'a, T> Iterator for InLevelTraversal<'a, T> {
+    type Item = (usize, NodeId);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (level, id) = self.queue.pop_front()?;
+        for child in self.tree.children(id) {
+            self.queue.push_back((level + 1, *child));
         }
+        Some((level, id))
     }
 }
 
 
 #[cfg(test)]
 mod tests {
-    use std::sync::Arc;
-
     use super::*;
 
-
-    #[test]
-    fn test_tree_low_level_node_manipulation() {
-        let child_node = Node::new(3);
-
-        {
-            let parent_node = Node::new(5);
-            parent_node.add_child_and_update_its_parent(&child_node);
-
-            assert_eq!(parent_node.children.read().unwrap().len(), 1);
-            assert!(parent_node.get_parent().is_none());
-            assert_eq!(*parent_node.value.read().unwrap(), 5);
-            assert_eq!(Arc::weak_count(&parent_node.arc_ref), 1);
-
-            println!("{}: {:#?}", "[parent_node]", parent_node);
-            println!("{}: {:#?}", "[child_node]", child_node);
-
-            assert_eq!(Arc::strong_count(&child_node.get_copy_of_internal_arc()), 3);
-            assert_eq!(Arc::weak_count(&child_node.get_copy_of_internal_arc()), 0);
-
-            assert_eq!(Arc::strong_count(&parent_node.get_copy_of_internal_arc()), 2);
-            assert_eq!(Arc::weak_count(&parent_node.get_copy_of_internal_arc()), 1);
-
-            assert!(child_node.get_parent().is_some());
-            assert_eq!(*child_node.get_copy_of_internal_arc().value.read().unwrap(), 3);
-        } // Parent is dropped here
-
-        // Child now has no parent, it's an orphan
-        assert!(child_node.get_parent().is_none());
-        assert_eq!(*child_node.get_copy_of_internal_arc().value.read().unwrap(), 3);
-        
-        assert_eq!(Arc::strong_count(&child_node.get_copy_of_internal_arc()), 2);
-        assert_eq!(Arc::weak_count(&child_node.get_copy_of_internal_arc()), 0);
+    //         1
+    //      /  |   \
+    //    10  20   30
+    //    /        |
+    //  100       200
+    //   |      /  |  \
+    //  1k    2k  3k   4k
+    fn construct_tree() -> Tree<i32> {
+        let mut tree = Tree::new(1);
+        let root = tree.root();
+
+        let child = tree.add_child(root, 10);
+        let sub_child = tree.add_child(child, 100);
+        tree.add_child(sub_child, 1000);
+
+        tree.add_child(root, 20);
+        let child = tree.add_child(root, 30);
+        let sub_child = tree.add_child(child, 200);
+        tree.add_child(sub_child, 2000);
+        tree.add_child(sub_child, 3000);
+        tree.add_child(sub_child, 4000);
+        tree
     }
 
     #[test]
     fn tree_simple_api() {
-        let root_node = Node::new(5);
-        assert_eq!(*root_node.get_copy_of_internal_arc().value.read().unwrap(), 5);
-
-        {
-            // In the following line 'Node' is returned by 'create_and_add_child()'.
-            // Instead a ref ('Arc') to the underlying 'NodeData' is returned
-
-            let child_node_data_ref = root_node.create_and_add_child(3);
-            let child_val = *child_node_data_ref.value.read().unwrap();
-            assert_eq!(child_val, 3);
-            assert_eq!(
-                root_node.get_copy_of_internal_arc().children.read().unwrap().len(),
-                1
-            );
-            assert_eq!(
-                child_val, 
-                *root_node.get_copy_of_internal_arc().children.read().unwrap()[0].value.read().unwrap()
-            )
-        }
-        println!("{}: {:#?}", "[tree]", root_node);        
+        let mut tree = Tree::new(5);
+        let root = tree.root();
+        let child = tree.add_child(root, 3);
+
+        assert_eq!(*tree.get(child), 3);
+        assert_eq!(tree.children(root), &[child]);
+        assert_eq!(tree.parent(child), Some(root));
+        assert_eq!(tree.parent(root), None);
+
+        *tree.get_mut(child) = 4;
+        assert_eq!(*tree.get(tree.children(root)[0]), 4);
+        assert_eq!(tree.len(), 2);
     }
 
     #[test]
     fn test_inrevorder_iter() {
-        let root_node = Node::new(1);
-        root_node.create_and_add_child(10);
-        root_node.create_and_add_child(20);
-        let child = Node { arc_ref: root_node.create_and_add_child(30)};
-        child.create_and_add_child(100);
-        child.create_and_add_child(200);
-        child.create_and_add_child(300);
-
-        //         1
-        //      /  |   \
-        //    10  20   30
-        //            / | \
-        //         100 200 300
-
-        let res: Vec<_> = root_node.inrevorder_iter().map(|child| *child.value.read().unwrap() ).collect();
+        let mut tree = Tree::new(1);
+        let root = tree.root();
+        tree.add_child(root, 10);
+        tree.add_child(root, 20);
+        let child = tree.add_child(root, 30);
+        tree.add_child(child, 100);
+        tree.add_child(child, 200);
+        tree.add_child(child, 300);
+
+        let res: Vec<_> = tree.inrevorder_iter(root).map(|id| *tree.get(id)).collect();
         assert_eq!(res, vec![10, 20, 100, 200, 300, 30, 1]);
     }
 
     #[test]
     fn test_inrevorder_iter_02() {
-        let root_node = Node::new(1);
-
-        let child_01 = Node { arc_ref: root_node.create_and_add_child(10) };
-        let sub_child_01 = Node { arc_ref: child_01.create_and_add_child(100)};
-        sub_child_01.create_and_add_child(1000);
-        
-        root_node.create_and_add_child(20);
-        let child = Node { arc_ref: root_node.create_and_add_child(30)};
-        let sub_child = Node {arc_ref: child.create_and_add_child(200)};
-        sub_child.create_and_add_child(2000);
-        sub_child.create_and_add_child(3000);
-        sub_child.create_and_add_child(4000);
-
-        //         1
-        //      /  |   \
-        //    10  20   30
-        //    /        |
-        //  100       200  
-        //   |      /  |  \
-        //  1k    2k  3k   4k
-
-        let res: Vec<_> = root_node.inrevorder_iter().map(|child| *child.value.read().unwrap() ).collect();
+        let tree = construct_tree();
+        let res: Vec<_> = tree.inrevorder_iter(tree.root()).map(|id| *tree.get(id)).collect();
         assert_eq!(res, vec![1000, 100, 10, 20, 2000, 3000, 4000, 200, 30, 1]);
     }
 
     #[test]
     fn test_inorder_iter() {
-        let root_node = Node::new(1);
-
-        let child_01 = Node { arc_ref: root_node.create_and_add_child(10) };
-        let sub_child_01 = Node { arc_ref: child_01.create_and_add_child(100)};
-        sub_child_01.create_and_add_child(1000);
-        
-        root_node.create_and_add_child(20);
-        let child = Node { arc_ref: root_node.create_and_add_child(30)};
-        let sub_child = Node {arc_ref: child.create_and_add_child(200)};
-        sub_child.create_and_add_child(2000);
-        sub_child.create_and_add_child(3000);
-        sub_child.create_and_add_child(4000);
-
-        //         1
-        //      /  |   \
-        //    10  20   30
-        //    /        |
-        //  100       200  
-        //   |      /  |  \
-        //  1k    2k  3k   4k
-
-        let res: Vec<_> = root_node.inorder_iter().map(|(level, child)| (level, *child.value.read().unwrap()) ).collect();
+        let tree = construct_tree();
+        let res: Vec<_> = tree.inorder_iter(tree.root()).map(|(level, id)| (level, *tree.get(id))).collect();
         assert_eq!(res, vec![
-            (0, 1), 
-            (1, 10), 
-            (2, 100), 
+            (0, 1),
+            (1, 10),
+            (2, 100),
             (3, 1000),
             (1, 20), (1, 30),
-            (2, 200), 
+            (2, 200),
             (3, 2000), (3, 3000), (3, 4000)
         ]);
     }
 
     #[test]
     fn test_inlevel_iter() {
-        let root_node = Node::new(1);
-
-        let child = Node { arc_ref: root_node.create_and_add_child(10) };
-        let sub_child = Node { arc_ref: child.create_and_add_child(100)};
-        sub_child.create_and_add_child(1000);
-        
-        root_node.create_and_add_child(20);
-        let child = Node { arc_ref: root_node.create_and_add_child(30)};
-        let sub_child = Node {arc_ref: child.create_and_add_child(200)};
-        sub_child.create_and_add_child(2000);
-        sub_child.create_and_add_child(3000);
-        sub_child.create_and_add_child(4000);
-
-        //         1
-        //      /  |   \
-        //    10  20   30
-        //    /        |
-        //  100       200  
-        //   |      /  |  \
-        //  1k    2k  3k   4k
-
-        let res: Vec<_> = root_node.inlevel_iter().map(|(level, child)| (level, *child.value.read().unwrap()) ).collect();
+        let tree = construct_tree();
+        let res: Vec<_> = tree.inlevel_iter(tree.root()).map(|(level, id)| (level, *tree.get(id))).collect();
         assert_eq!(res, vec![
             (0, 1),
             (1, 10), (1, 20), (1, 30),
             (2, 100), (2, 200),
             (3, 1000), (3, 2000), (3, 3000), (3, 4000)
-        ]);        
+        ]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_fold_postorder() {
+        let tree = construct_tree();
+        let sums = tree.fold_postorder(tree.root(), |_id, value, children| {
+            value + children.iter().sum::<i32>()
+        });
+        let sums: Vec<_> = sums.into_iter().map(|(id, sum)| (*tree.get(id), sum)).collect();
+        assert_eq!(sums, vec![
+            (1000, 1000), (100, 1100), (10, 1110), (20, 20),
+            (2000, 2000), (3000, 3000), (4000, 4000), (200, 9200), (30, 9230),
+            (1, 10361)
+        ]);
+    }
+}